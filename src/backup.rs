@@ -0,0 +1,261 @@
+use crate::gpt;
+use crate::subr;
+use crate::Result;
+
+// Layout is compatible with sgdisk --backup, i.e. protective MBR, primary
+// header, secondary header and primary entries, each starting at a sector
+// boundary. Secondary entries and a trailer describing the device follow,
+// which sgdisk ignores.
+const BACKUP_MAGIC: [u8; 16] = *b"DUMPGPT-BACKUP\0\0";
+const BACKUP_VERSION: u32 = 1;
+const TRAILER_SIZE: usize = 512;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Backup {
+    pub(crate) mbr: Vec<u8>,
+    pub(crate) hdr1: Vec<u8>,
+    pub(crate) hdr2: Vec<u8>,
+    pub(crate) tbl1: Vec<u8>,
+    pub(crate) tbl2: Vec<u8>,
+    pub(crate) sector_size: u64,
+    pub(crate) device_size: u64,
+}
+
+pub(crate) fn read_backup(fp: &mut std::fs::File, sector_size: u64) -> Result<Backup> {
+    let ss = sector_size;
+    let n = usize::try_from(ss)?;
+    let g1 = gpt::read_gpt_at(fp, 1, ss)?;
+    let g2 = gpt::read_gpt_at(fp, g1.hdr.hdr_lba_alt, ss)?;
+    Ok(Backup {
+        mbr: gpt::read_at(fp, 0, n)?,
+        hdr1: gpt::read_at(fp, ss, n)?,
+        hdr2: gpt::read_at(fp, gpt::get_offset(g1.hdr.hdr_lba_alt, ss)?, n)?,
+        tbl1: gpt::read_table(fp, &g1.hdr, ss)?,
+        tbl2: gpt::read_table(fp, &g2.hdr, ss)?,
+        sector_size: ss,
+        device_size: subr::get_size(fp)?,
+    })
+}
+
+pub(crate) fn backup_to_bytes(b: &Backup) -> Vec<u8> {
+    let mut buf = vec![];
+    for x in [&b.mbr, &b.hdr1, &b.hdr2, &b.tbl1, &b.tbl2] {
+        buf.extend_from_slice(x);
+    }
+    let mut t = vec![0; TRAILER_SIZE];
+    t[..16].copy_from_slice(&BACKUP_MAGIC);
+    t[16..20].copy_from_slice(&BACKUP_VERSION.to_le_bytes());
+    t[24..32].copy_from_slice(&b.sector_size.to_le_bytes());
+    t[32..40].copy_from_slice(&b.device_size.to_le_bytes());
+    t[40..44].copy_from_slice(&subr::crc32(&buf).to_le_bytes());
+    buf.extend_from_slice(&t);
+    buf
+}
+
+fn get_slice(buf: &[u8], offset: u64, size: u64) -> Result<Vec<u8>> {
+    let Some(end) = offset.checked_add(size) else {
+        return Err(format!("backup offset {offset} out of range").into());
+    };
+    match buf.get(usize::try_from(offset)?..usize::try_from(end)?) {
+        Some(v) => Ok(v.to_vec()),
+        None => Err(format!("backup truncated at {offset}-{end}").into()),
+    }
+}
+
+pub(crate) fn backup_from_bytes(buf: &[u8]) -> Result<Backup> {
+    let trailer = buf
+        .len()
+        .checked_sub(TRAILER_SIZE)
+        .map(|x| (&buf[..x], &buf[x..]))
+        .filter(|x| x.1[..16] == BACKUP_MAGIC);
+
+    let (ss, device_size) = if let Some((data, t)) = trailer {
+        let version = u32::from_le_bytes(t[16..20].try_into()?);
+        if version != BACKUP_VERSION {
+            return Err(format!("unsupported backup version {version}").into());
+        }
+        if u32::from_le_bytes(t[40..44].try_into()?) != subr::crc32(data) {
+            return Err("backup CRC mismatch".into());
+        }
+        (
+            u64::from_le_bytes(t[24..32].try_into()?),
+            u64::from_le_bytes(t[32..40].try_into()?),
+        )
+    } else {
+        // sgdisk backup, detect sector size by primary header location
+        let Some(ss) = [512, 4096]
+            .into_iter()
+            .find(|x| get_slice(buf, *x, 8).is_ok_and(|v| v == gpt::GPT_SIG))
+        else {
+            return Err("not a GPT backup".into());
+        };
+        let hdr = gpt::parse_header(&get_slice(buf, ss, ss)?)?;
+        gpt::check_header(&hdr)?;
+        let Some(size) = hdr
+            .hdr_lba_alt
+            .checked_add(1)
+            .and_then(|x| x.checked_mul(ss))
+        else {
+            return Err(format!("invalid secondary header location {}", hdr.hdr_lba_alt).into());
+        };
+        (ss, size)
+    };
+    if !ss.is_power_of_two() || !(512..=65536).contains(&ss) {
+        return Err(format!("invalid sector size {ss}").into());
+    }
+
+    let hdr1 = get_slice(buf, ss, ss)?;
+    let hdr2 = get_slice(buf, 2 * ss, ss)?;
+    let h1 = gpt::parse_header(&hdr1)?;
+    let h2 = gpt::parse_header(&hdr2)?;
+    gpt::check_header(&h1)?;
+    gpt::check_header(&h2)?;
+    if h1.hdr_lba_self != 1 || h2.hdr_lba_self != h1.hdr_lba_alt {
+        return Err("inconsistent header locations".into());
+    }
+    let size = u64::try_from(gpt::get_table_bytes(&h1, ss)?)?;
+    let tbl1 = get_slice(buf, 3 * ss, size)?;
+    let tbl2 = if trailer.is_some() {
+        get_slice(buf, 3 * ss + size, size)?
+    } else {
+        tbl1.clone()
+    };
+    if h1.hdr_crc_table != gpt::get_table_crc(&tbl1, &h1)?
+        || h2.hdr_crc_table != gpt::get_table_crc(&tbl2, &h2)?
+    {
+        return Err("entries CRC mismatch".into());
+    }
+
+    Ok(Backup {
+        mbr: get_slice(buf, 0, ss)?,
+        hdr1,
+        hdr2,
+        tbl1,
+        tbl2,
+        sector_size: ss,
+        device_size,
+    })
+}
+
+pub(crate) fn write_backup(fp: &mut std::fs::File, b: &Backup) -> Result<()> {
+    let ss = b.sector_size;
+    let size = subr::get_size(fp)?;
+    if size != b.device_size {
+        eprintln!(
+            "warning: device size {size} differs from backup {}",
+            b.device_size
+        );
+    }
+    let h1 = gpt::parse_header(&b.hdr1)?;
+    let h2 = gpt::parse_header(&b.hdr2)?;
+    if h2
+        .hdr_lba_self
+        .checked_add(1)
+        .and_then(|x| x.checked_mul(ss))
+        .is_none_or(|x| x > size)
+    {
+        return Err(format!(
+            "device too small for secondary header at {}",
+            h2.hdr_lba_self
+        )
+        .into());
+    }
+    // all offsets are checked before anything is written
+    let v = [
+        (gpt::get_offset(h2.hdr_lba_table, ss)?, &b.tbl2),
+        (gpt::get_offset(h2.hdr_lba_self, ss)?, &b.hdr2),
+        (gpt::get_offset(h1.hdr_lba_table, ss)?, &b.tbl1),
+        (gpt::get_offset(h1.hdr_lba_self, ss)?, &b.hdr1),
+    ];
+
+    gpt::write_at(fp, 0, &b.mbr)?;
+    for (offset, x) in v {
+        gpt::write_at(fp, offset, x)?;
+    }
+    fp.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit;
+    use crate::gpt;
+
+    #[test]
+    fn test_backup() {
+        let (f, mut fp) = edit::tests::create_image("backup", 64 << 20);
+        let opt = edit::tests::get_opt();
+        let mut g = edit::create_gpt(&mut fp, &opt).unwrap();
+        edit::add_entry(&mut g, &opt).unwrap();
+        gpt::write_gpt(&mut fp, &mut g).unwrap();
+
+        let b = super::read_backup(&mut fp, 512).unwrap();
+        assert_eq!(b.device_size, 64 << 20);
+        let buf = super::backup_to_bytes(&b);
+        assert_eq!(buf.len(), 3 * 512 + 2 * 16384 + 512);
+        assert_eq!(super::backup_from_bytes(&buf).unwrap(), b);
+
+        // wipe and restore
+        fp.set_len(0).unwrap();
+        fp.set_len(64 << 20).unwrap();
+        assert!(gpt::read_gpt(&mut fp, 512).is_err());
+        super::write_backup(&mut fp, &b).unwrap();
+        let x = gpt::read_gpt(&mut fp, 512).unwrap();
+        assert_eq!(x.hdr.hdr_crc_self, g.hdr.hdr_crc_self);
+        assert_eq!(x.ent, g.ent);
+        assert!(gpt::read_gpt_at(&mut fp, g.hdr.hdr_lba_alt, 512).is_ok());
+        assert_eq!(super::read_backup(&mut fp, 512).unwrap(), b);
+
+        // too small
+        fp.set_len(32 << 20).unwrap();
+        assert!(super::write_backup(&mut fp, &b).is_err());
+
+        // crafted secondary location
+        let mut x = super::backup_from_bytes(&buf).unwrap();
+        let mut h = gpt::parse_header(&x.hdr2).unwrap();
+        h.hdr_lba_self = u64::MAX;
+        x.hdr2[..92].copy_from_slice(&gpt::encode_header(&h));
+        assert!(super::write_backup(&mut fp, &x).is_err());
+        std::fs::remove_file(f).unwrap();
+    }
+
+    #[test]
+    fn test_backup_from_bytes() {
+        let (f, mut fp) = edit::tests::create_image("backup-bytes", 8 << 20);
+        let mut opt = edit::tests::get_opt();
+        opt.sector_size = 4096;
+        edit::create_gpt(&mut fp, &opt).unwrap();
+        let b = super::read_backup(&mut fp, 4096).unwrap();
+        let buf = super::backup_to_bytes(&b);
+        std::fs::remove_file(f).unwrap();
+
+        // sgdisk layout without secondary entries and trailer
+        let n = 3 * 4096 + 16384;
+        let x = super::backup_from_bytes(&buf[..n]).unwrap();
+        assert_eq!(x, b);
+
+        assert!(super::backup_from_bytes(&buf[..n - 1]).is_err());
+        assert!(super::backup_from_bytes(&[0; 8192]).is_err());
+        let mut v = buf.clone();
+        v[4096 + 100] ^= 1; // reserved area of primary header
+        assert!(super::backup_from_bytes(&v).is_err());
+        let mut v = buf[..n].to_vec();
+        v[4096 + 16] ^= 1; // primary header CRC
+        assert!(super::backup_from_bytes(&v).is_err());
+        let mut v = buf[..n].to_vec();
+        v[3 * 4096] ^= 1; // entries
+        assert!(super::backup_from_bytes(&v).is_err());
+
+        // crafted secondary location with a valid CRC
+        let mut v = buf[..n].to_vec();
+        let mut h = gpt::parse_header(&v[4096..]).unwrap();
+        h.hdr_lba_alt = u64::MAX;
+        h.hdr_crc_self = gpt::get_header_crc(&h);
+        v[4096..4096 + 92].copy_from_slice(&gpt::encode_header(&h));
+        let e = super::backup_from_bytes(&v).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!("invalid secondary header location {}", u64::MAX)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::edit;
+use crate::gpt;
+use crate::subr;
+use crate::uuid;
+use crate::Result;
+use std::fmt::Write;
+
+fn uuid_to_string(uuid: &crate::uuid::Uuid) -> String {
+    match subr::find_known_uuid(uuid) {
+        Some(x) => format!(
+            "{} ({})\n           {}: {}",
+            subr::uuid_to_str(uuid),
+            x.name,
+            x.os,
+            x.desc
+        ),
+        None => subr::uuid_to_str(uuid),
+    }
+}
+
+fn lba_to_string(lba: u64, sector_size: u64) -> String {
+    match lba.checked_mul(sector_size) {
+        Some(n) => format!("{lba} LBA, {n} bytes, {}", subr::format_size(n)),
+        None => format!("{lba} LBA"),
+    }
+}
+
+// inclusive, i.e. the last byte of the last sector
+fn end_to_string(lba: u64, sector_size: u64) -> String {
+    match lba.checked_add(1).and_then(|x| x.checked_mul(sector_size)) {
+        Some(n) => format!("{lba} LBA, last byte {}", n - 1),
+        None => format!("{lba} LBA"),
+    }
+}
+
+// byte offset of an entry, headers may be damaged, so None on overflow
+fn get_offset(hdr: &gpt::GptHdr, sector_size: u64, off: u64) -> Option<u64> {
+    hdr.hdr_lba_table.checked_mul(sector_size)?.checked_add(off)
+}
+
+// raw is the whole primary entries array as read from the device, alt is
+// the secondary header if it could be read and validated
+pub(crate) fn entry_to_string(
+    gpt: &gpt::Gpt,
+    alt: Option<&gpt::GptHdr>,
+    raw: &[u8],
+    i: usize,
+) -> Result<String> {
+    let hdr = &gpt.hdr;
+    edit::check_index(gpt, i)?;
+    let ss = gpt.sector_size;
+    let x = &gpt.ent[i];
+    let mut s = String::new();
+
+    writeln!(
+        s,
+        "entry    = {i}{}",
+        if x.is_unused() { " (unused)" } else { "" }
+    )?;
+    writeln!(s, "type     = {}", uuid_to_string(&x.ent_type))?;
+    for v in uuid::uuid_describe(&x.ent_type) {
+        writeln!(s, "           {v}")?;
+    }
+    writeln!(s, "uniq     = {}", subr::uuid_to_str(&x.ent_uuid))?;
+    for v in uuid::uuid_describe(&x.ent_uuid) {
+        writeln!(s, "           {v}")?;
+    }
+    writeln!(s, "start    = {}", lba_to_string(x.ent_lba_start, ss))?;
+    writeln!(s, "end      = {}", end_to_string(x.ent_lba_end, ss))?;
+    if let (false, Some(n)) = (x.is_unused(), x.get_sectors()) {
+        writeln!(s, "size     = {}", lba_to_string(n, ss))?;
+    }
+
+    writeln!(s, "attr     = 0x{:016x}", x.ent_attr)?;
+    for bit in 0..64 {
+        if x.ent_attr & (1 << bit) != 0 {
+            // reserved bits come back as a hex value rather than a name
+            let v = subr::attr_to_names(1 << bit, false);
+            let name = v
+                .first()
+                .filter(|x| !x.starts_with("0x"))
+                .map_or("reserved", |x| x.as_str());
+            writeln!(s, "           bit {bit:<2} {name}")?;
+        }
+    }
+
+    let name = gpt::ent_name_to_string(&x.ent_name);
+    writeln!(s, "name     = \"{}\"", name.escape_debug())?;
+    let n = x
+        .ent_name
+        .iter()
+        .position(|c| *c == 0)
+        .unwrap_or(x.ent_name.len());
+    let v: Vec<_> = char::decode_utf16(x.ent_name[..n].iter().copied())
+        .map(|r| match r {
+            Ok(c) => format!("U+{:04X}", u32::from(c)),
+            Err(e) => format!("U+{:04X}?", e.unpaired_surrogate()),
+        })
+        .collect();
+    if !v.is_empty() {
+        writeln!(s, "           {}", v.join(" "))?;
+    }
+
+    let entsz = u64::from(hdr.hdr_entsz);
+    let off = u64::try_from(i)? * entsz;
+    let primary = get_offset(hdr, ss, off);
+    let secondary = alt.and_then(|x| get_offset(x, ss, off));
+    let f = |v: Option<u64>| v.map_or("unknown".to_string(), |x| x.to_string());
+    writeln!(s, "offset   = {} (primary)", f(primary))?;
+    writeln!(s, "           {} (secondary)", f(secondary))?;
+
+    let start = usize::try_from(off)?;
+    let end = start + usize::try_from(entsz)?;
+    let Some(buf) = raw.get(start..end) else {
+        return Err(format!("entry {i} beyond entries array").into());
+    };
+    writeln!(s)?;
+    // relative to the entries array if the location is unknown
+    for line in subr::hexdump(buf, primary.unwrap_or(off)) {
+        writeln!(s, "{line}")?;
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit;
+    use crate::gpt;
+    use crate::subr;
+    use crate::uuid;
+
+    #[test]
+    fn test_entry_to_string() {
+        let (f, mut fp) = edit::tests::create_image("detail", 64 << 20);
+        let opt = edit::tests::get_opt();
+        let mut g = edit::create_gpt(&mut fp, &opt).unwrap();
+        let mut o = edit::tests::get_opt();
+        o.part = Some(2);
+        o.size = Some(1 << 20);
+        o.ptype = subr::known_str_to_uuid("EFI");
+        o.part_guid = Some(uuid::uuid_from_string("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap());
+        o.name = Some("ESP \u{e9}\u{1f600}".to_string());
+        o.attr = Some(1 | 1 << 5 | 1 << 60);
+        edit::add_entry(&mut g, &o).unwrap();
+        gpt::write_gpt(&mut fp, &mut g).unwrap();
+        let raw = gpt::read_table(&mut fp, &g.hdr, 512).unwrap();
+        let alt = gpt::read_header(&mut fp, g.hdr.hdr_lba_alt, 512).unwrap();
+        std::fs::remove_file(f).unwrap();
+
+        let s = super::entry_to_string(&g, Some(&alt), &raw, 2).unwrap();
+        let v: Vec<_> = s.lines().collect();
+        assert_eq!(
+            v[..23],
+            [
+                "entry    = 2",
+                "type     = c12a7328-f81f-11d2-ba4b-00a0c93ec93b (EFI)",
+                "           Generic: EFI system partition",
+                "           variant RFC 4122, version 1 (time-based)",
+                "           time 1999-04-21 19:24:01.5625000 UTC",
+                "           clock_seq 14923, node 00:a0:c9:3e:c9:3b",
+                "uniq     = 516e7cb4-6ecf-11d6-8ff8-00022d09712b",
+                "           variant RFC 4122, version 1 (time-based)",
+                "           time 2002-05-24 04:32:55.7489332 UTC",
+                "           clock_seq 4088, node 00:02:2d:09:71:2b",
+                "start    = 2048 LBA, 1048576 bytes, 1.0MiB",
+                "end      = 4095 LBA, last byte 2097151",
+                "size     = 2048 LBA, 1048576 bytes, 1.0MiB",
+                "attr     = 0x1000000000000021",
+                "           bit 0  required",
+                "           bit 5  reserved",
+                "           bit 60 guid:60",
+                "name     = \"ESP \u{e9}\u{1f600}\"",
+                "           U+0045 U+0053 U+0050 U+0020 U+00E9 U+1F600",
+                "offset   = 1280 (primary)",
+                "           67092224 (secondary)",
+                "",
+                "00000500  28 73 2a c1 1f f8 d2 11  ba 4b 00 a0 c9 3e c9 3b  |(s*......K...>.;|",
+            ]
+        );
+        assert_eq!(v.len(), 23 + 7);
+
+        let s = super::entry_to_string(&g, Some(&alt), &raw, 0).unwrap();
+        assert!(s.starts_with("entry    = 0 (unused)\n"));
+        assert_eq!(s.matches("           nil UUID\n").count(), 2);
+        assert!(!s.contains("size"));
+        assert!(super::entry_to_string(&g, Some(&alt), &raw, 128).is_err());
+        assert!(super::entry_to_string(&g, Some(&alt), &raw[..256], 2).is_err());
+
+        // secondary offset comes from the secondary header as found on disk
+        let mut moved = alt;
+        moved.hdr_lba_table -= 1;
+        let s = super::entry_to_string(&g, Some(&moved), &raw, 2).unwrap();
+        assert!(s.contains("\n           67091712 (secondary)\n"));
+        let s = super::entry_to_string(&g, None, &raw, 2).unwrap();
+        assert!(s.contains("\n           unknown (secondary)\n"));
+        moved.hdr_lba_table = u64::MAX;
+        let s = super::entry_to_string(&g, Some(&moved), &raw, 2).unwrap();
+        assert!(s.contains("\n           unknown (secondary)\n"));
+        let mut h = g.clone();
+        h.hdr.hdr_lba_table = u64::MAX;
+        let s = super::entry_to_string(&h, None, &raw, 2).unwrap();
+        assert!(s.contains("\noffset   = unknown (primary)\n"));
+        assert!(s.contains("\n00000100  28 73 2a c1"));
+        h.ent[2].ent_lba_end = u64::MAX;
+        let s = super::entry_to_string(&h, None, &raw, 2).unwrap();
+        assert!(s.contains(&format!("\nend      = {} LBA\n", u64::MAX)));
+
+        g.ent.clear();
+        assert_eq!(
+            super::entry_to_string(&g, None, &raw, 0)
+                .unwrap_err()
+                .to_string(),
+            "no entries"
+        );
+    }
+}
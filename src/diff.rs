@@ -0,0 +1,278 @@
+use crate::gpt;
+use crate::subr;
+use crate::Opt;
+
+// text and JSON representation of a field value
+pub(crate) type FieldValue = (String, serde_json::Value);
+
+// field name, old value, new value
+pub(crate) type FieldDiff = (&'static str, FieldValue, FieldValue);
+
+#[derive(Debug, Default)]
+pub(crate) struct GptDiff {
+    pub(crate) hdr: Vec<FieldDiff>,
+    pub(crate) added: Vec<usize>,   // index in b
+    pub(crate) removed: Vec<usize>, // index in a
+    pub(crate) changed: Vec<(usize, usize, Vec<FieldDiff>)>, // index in a and b
+}
+
+impl GptDiff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.hdr.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+fn push_field(v: &mut Vec<FieldDiff>, name: &'static str, a: FieldValue, b: FieldValue) {
+    if a.0 != b.0 {
+        v.push((name, a, b));
+    }
+}
+
+fn str_field(s: String) -> FieldValue {
+    (s.clone(), s.into())
+}
+
+fn num_field(x: impl Into<u64>) -> FieldValue {
+    let x = x.into();
+    (x.to_string(), serde_json::Value::from(x))
+}
+
+fn get_header_fields(hdr: &gpt::GptHdr, opt: &Opt) -> [(&'static str, FieldValue); 9] {
+    // CRCs change along with any other field, so they are not compared
+    [
+        ("revision", str_field(format!("0x{:08x}", hdr.hdr_revision))),
+        ("size", num_field(hdr.hdr_size)),
+        ("lba_alt", num_field(hdr.hdr_lba_alt)),
+        ("lba_start", num_field(hdr.hdr_lba_start)),
+        ("lba_end", num_field(hdr.hdr_lba_end)),
+        (
+            "uuid",
+            str_field(gpt::try_known_uuid_to_str(&hdr.hdr_uuid, opt)),
+        ),
+        ("lba_table", num_field(hdr.hdr_lba_table)),
+        ("entries", num_field(hdr.hdr_entries)),
+        ("entsz", num_field(hdr.hdr_entsz)),
+    ]
+}
+
+fn get_entry_fields(ent: &gpt::GptEnt, opt: &Opt) -> [(&'static str, FieldValue); 5] {
+    [
+        (
+            "type",
+            str_field(gpt::try_known_uuid_to_str(&ent.ent_type, opt)),
+        ),
+        ("lba_start", num_field(ent.ent_lba_start)),
+        ("lba_end", num_field(ent.ent_lba_end)),
+        (
+            "attr",
+            (format!("0x{:016x}", ent.ent_attr), ent.ent_attr.into()),
+        ),
+        ("name", str_field(gpt::ent_name_to_string(&ent.ent_name))),
+    ]
+}
+
+// entries are matched by unique GUID
+pub(crate) fn diff_gpt(a: &gpt::Gpt, b: &gpt::Gpt, opt: &Opt) -> GptDiff {
+    let mut d = GptDiff::default();
+    for (x, y) in get_header_fields(&a.hdr, opt)
+        .into_iter()
+        .zip(get_header_fields(&b.hdr, opt))
+    {
+        push_field(&mut d.hdr, x.0, x.1, y.1);
+    }
+
+    for (i, x) in a.ent.iter().enumerate() {
+        if x.is_unused() {
+            continue;
+        }
+        match b
+            .ent
+            .iter()
+            .position(|y| !y.is_unused() && y.ent_uuid == x.ent_uuid)
+        {
+            Some(j) => {
+                let mut v = vec![];
+                if i != j {
+                    v.push((
+                        "index",
+                        (i.to_string(), i.into()),
+                        (j.to_string(), j.into()),
+                    ));
+                }
+                for (p, q) in get_entry_fields(x, opt)
+                    .into_iter()
+                    .zip(get_entry_fields(&b.ent[j], opt))
+                {
+                    push_field(&mut v, p.0, p.1, q.1);
+                }
+                if !v.is_empty() {
+                    d.changed.push((i, j, v));
+                }
+            }
+            None => d.removed.push(i),
+        }
+    }
+    for (j, y) in b.ent.iter().enumerate() {
+        if !y.is_unused()
+            && !a
+                .ent
+                .iter()
+                .any(|x| !x.is_unused() && x.ent_uuid == y.ent_uuid)
+        {
+            d.added.push(j);
+        }
+    }
+    d
+}
+
+fn entry_to_string(i: usize, ent: &gpt::GptEnt, opt: &Opt) -> String {
+    format!(
+        "entry {i} {} {} {}-{} 0x{:016x} \"{}\"",
+        subr::uuid_to_str(&ent.ent_uuid),
+        gpt::try_known_uuid_to_str(&ent.ent_type, opt),
+        ent.ent_lba_start,
+        ent.ent_lba_end,
+        ent.ent_attr,
+        gpt::ent_name_to_string(&ent.ent_name)
+    )
+}
+
+pub(crate) fn print_diff(d: &GptDiff, a: &gpt::Gpt, b: &gpt::Gpt, opt: &Opt) {
+    for (name, x, y) in &d.hdr {
+        println!("~ header {name}: {} -> {}", x.0, y.0);
+    }
+    for i in &d.removed {
+        println!("- {}", entry_to_string(*i, &a.ent[*i], opt));
+    }
+    for j in &d.added {
+        println!("+ {}", entry_to_string(*j, &b.ent[*j], opt));
+    }
+    for (i, _, v) in &d.changed {
+        println!("~ entry {i} {}", subr::uuid_to_str(&a.ent[*i].ent_uuid));
+        for (name, x, y) in v {
+            println!("    {name}: {} -> {}", x.0, y.0);
+        }
+    }
+}
+
+fn entry_to_json(i: usize, ent: &gpt::GptEnt, opt: &Opt) -> serde_json::Value {
+    let mut m = serde_json::Map::new();
+    m.insert("index".to_string(), i.into());
+    m.insert("uuid".to_string(), subr::uuid_to_str(&ent.ent_uuid).into());
+    for (name, x) in get_entry_fields(ent, opt) {
+        m.insert(name.to_string(), x.1);
+    }
+    m.into()
+}
+
+fn fields_to_json(v: &[FieldDiff]) -> serde_json::Value {
+    v.iter()
+        .map(|(name, x, y)| serde_json::json!({"field": name, "a": x.1, "b": y.1}))
+        .collect()
+}
+
+pub(crate) fn diff_to_json(
+    d: &GptDiff,
+    a: &gpt::Gpt,
+    b: &gpt::Gpt,
+    opt: &Opt,
+) -> serde_json::Value {
+    serde_json::json!({
+        "header": fields_to_json(&d.hdr),
+        "removed": d.removed.iter().map(|i| entry_to_json(*i, &a.ent[*i], opt)).collect::<Vec<_>>(),
+        "added": d.added.iter().map(|j| entry_to_json(*j, &b.ent[*j], opt)).collect::<Vec<_>>(),
+        "changed": d.changed.iter().map(|(i, j, v)| serde_json::json!({
+            "uuid": subr::uuid_to_str(&a.ent[*i].ent_uuid),
+            "index_a": i,
+            "index_b": j,
+            "fields": fields_to_json(v),
+        })).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit;
+    use crate::uuid;
+
+    #[test]
+    fn test_diff_gpt() {
+        let opt = edit::tests::get_opt();
+        let mut a = edit::tests::new_gpt();
+        for _ in 0..3 {
+            let mut o = edit::tests::get_opt();
+            o.size = Some(1 << 20);
+            edit::add_entry(&mut a, &o).unwrap();
+        }
+        let d = super::diff_gpt(&a, &a, &opt);
+        assert!(d.is_empty());
+
+        let mut b = a.clone();
+        b.hdr.hdr_uuid = uuid::uuid_random().unwrap();
+        b.ent[0] = crate::gpt::GptEnt::new(); // removed
+        b.ent[1].ent_attr = 4; // changed
+        b.ent[1].ent_name = crate::gpt::str_to_ent_name("root").unwrap();
+        b.ent.swap(2, 5); // moved
+        let mut o = edit::tests::get_opt();
+        o.part = Some(7);
+        edit::add_entry(&mut b, &o).unwrap(); // added
+
+        let d = super::diff_gpt(&a, &b, &opt);
+        assert!(!d.is_empty());
+        assert_eq!(d.hdr.len(), 1);
+        assert_eq!(d.hdr[0].0, "uuid");
+        assert_eq!(d.removed, [0]);
+        assert_eq!(d.added, [7]);
+        assert_eq!(d.changed.len(), 2);
+        assert_eq!(d.changed[0].0, 1);
+        assert_eq!(d.changed[0].1, 1);
+        assert_eq!(
+            d.changed[0].2,
+            [
+                (
+                    "attr",
+                    (format!("0x{:016x}", 0), 0.into()),
+                    (format!("0x{:016x}", 4), 4.into())
+                ),
+                (
+                    "name",
+                    (String::new(), "".into()),
+                    ("root".to_string(), "root".into())
+                )
+            ]
+        );
+        assert_eq!(
+            d.changed[1],
+            (
+                2,
+                5,
+                vec![(
+                    "index",
+                    ("2".to_string(), 2.into()),
+                    ("5".to_string(), 5.into())
+                )]
+            )
+        );
+
+        let j = super::diff_to_json(&d, &a, &b, &opt);
+        assert_eq!(j["header"][0]["field"], "uuid");
+        assert_eq!(j["removed"][0]["index"], 0);
+        assert_eq!(j["added"][0]["index"], 7);
+        assert_eq!(j["added"][0]["lba_start"], b.ent[7].ent_lba_start);
+        assert_eq!(j["added"][0]["lba_end"], b.ent[7].ent_lba_end);
+        assert_eq!(j["added"][0]["attr"], 0);
+        assert_eq!(j["changed"][1]["index_b"], 5);
+        assert_eq!(j["changed"][1]["fields"][0]["b"], 5);
+        assert_eq!(j["changed"][0]["fields"][0]["a"], 0);
+        assert_eq!(j["changed"][0]["fields"][0]["b"], 4);
+        assert_eq!(j["changed"][0]["fields"][1]["b"], "root");
+
+        // large values must stay exact, not go through a float
+        b.ent[7].ent_attr = u64::MAX;
+        let j = super::diff_to_json(&super::diff_gpt(&a, &b, &opt), &a, &b, &opt);
+        assert_eq!(j["added"][0]["attr"].as_u64(), Some(u64::MAX));
+    }
+}
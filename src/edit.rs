@@ -0,0 +1,880 @@
+use crate::gpt;
+use crate::subr;
+use crate::uuid;
+use crate::Opt;
+use crate::Result;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+// name-based GUID derived from --seed, so that the same input always
+// produces the same table, otherwise random
+fn new_guid(opt: &Opt, name: &str) -> Result<uuid::Uuid> {
+    match &opt.seed {
+        Some(v) => Ok(uuid::uuid_v5(
+            &uuid::NAMESPACE_SEED,
+            format!("{v}/{name}").as_bytes(),
+        )),
+        None => uuid::uuid_random(),
+    }
+}
+
+pub(crate) fn init_gpt(size: u64, opt: &Opt) -> Result<gpt::Gpt> {
+    let ss = opt.sector_size;
+    let table_size = u64::from(opt.entries) * u64::from(gpt::GPT_ENT_SIZE);
+    if opt.entries == 0 || table_size % ss != 0 {
+        return Err(format!(
+            "number of entries {} must fill {ss} bytes sectors",
+            opt.entries
+        )
+        .into());
+    }
+    let table_sectors = table_size / ss;
+
+    // protective MBR, 2 headers, 2 tables and at least 1 usable sector
+    let lbas = size / ss;
+    if lbas < 3 + 2 * table_sectors + 1 {
+        return Err(format!("device too small ({lbas} sectors)").into());
+    }
+
+    let mut hdr = gpt::GptHdr::new();
+    hdr.hdr_sig = gpt::GPT_SIG;
+    hdr.hdr_revision = gpt::GPT_REVISION;
+    hdr.hdr_size = gpt::GPT_HDR_SIZE;
+    hdr.hdr_lba_self = 1;
+    hdr.hdr_lba_alt = lbas - 1;
+    hdr.hdr_lba_start = 2 + table_sectors;
+    hdr.hdr_lba_end = lbas - 2 - table_sectors;
+    hdr.hdr_uuid = match opt.disk_guid {
+        Some(v) => v,
+        None => new_guid(opt, "disk")?,
+    };
+    hdr.hdr_lba_table = 2;
+    hdr.hdr_entries = opt.entries;
+    hdr.hdr_entsz = gpt::GPT_ENT_SIZE;
+
+    Ok(gpt::Gpt {
+        hdr,
+        ent: vec![gpt::GptEnt::new(); usize::try_from(opt.entries)?],
+        sector_size: ss,
+    })
+}
+
+pub(crate) fn create_gpt(fp: &mut std::fs::File, opt: &Opt) -> Result<gpt::Gpt> {
+    let mut gpt = init_gpt(subr::get_size(fp)?, opt)?;
+    gpt::write_pmbr(fp, &gpt)?;
+    gpt::write_gpt(fp, &mut gpt)?;
+    gpt::read_gpt(fp, opt.sector_size) // verify
+}
+
+fn align_up(lba: u64, align: u64) -> u64 {
+    lba.div_ceil(align) * align
+}
+
+// last LBA of an entry of at least size bytes, rounded up so that the next
+// LBA is aligned, unless that passes the end of usable range
+fn get_size_end(gpt: &gpt::Gpt, start: u64, size: u64, align: u64) -> u64 {
+    let end = start.saturating_add(size.div_ceil(gpt.sector_size).saturating_sub(1));
+    match end
+        .saturating_add(1)
+        .div_ceil(align)
+        .checked_mul(align)
+        .map(|x| x - 1)
+    {
+        Some(x) if x <= gpt.hdr.hdr_lba_end => x,
+        _ => end,
+    }
+}
+
+pub(crate) fn check_index(gpt: &gpt::Gpt, i: usize) -> Result<()> {
+    match gpt.ent.len() {
+        0 => Err("no entries".into()),
+        n if i >= n => Err(format!("entry {i} out of range (0-{})", n - 1).into()),
+        _ => Ok(()),
+    }
+}
+
+fn get_used_index(gpt: &gpt::Gpt, opt: &Opt) -> Result<usize> {
+    let Some(i) = opt.part else {
+        return Err("entry not specified (--part)".into());
+    };
+    check_index(gpt, i)?;
+    if gpt.ent[i].is_unused() {
+        return Err(format!("entry {i} unused").into());
+    }
+    Ok(i)
+}
+
+fn check_range(gpt: &gpt::Gpt, start: u64, end: u64, skip: Option<usize>) -> Result<()> {
+    if start > end {
+        return Err(format!("invalid range {start}-{end}").into());
+    }
+    if start < gpt.hdr.hdr_lba_start || end > gpt.hdr.hdr_lba_end {
+        return Err(format!(
+            "range {start}-{end} outside usable range {}-{}",
+            gpt.hdr.hdr_lba_start, gpt.hdr.hdr_lba_end
+        )
+        .into());
+    }
+    for (i, x) in gpt.ent.iter().enumerate() {
+        if Some(i) == skip || x.is_unused() {
+            continue;
+        }
+        if start <= x.ent_lba_end && x.ent_lba_start <= end {
+            return Err(format!("range {start}-{end} overlaps entry {i}").into());
+        }
+    }
+    Ok(())
+}
+
+fn check_uuid(gpt: &gpt::Gpt, uuid: &uuid::Uuid, skip: Option<usize>) -> Result<()> {
+    for (i, x) in gpt.ent.iter().enumerate() {
+        if Some(i) != skip && !x.is_unused() && x.ent_uuid == *uuid {
+            return Err(format!("GUID {} used by entry {i}", subr::uuid_to_str(uuid)).into());
+        }
+    }
+    Ok(())
+}
+
+fn check_type(uuid: &uuid::Uuid) -> Result<()> {
+    if *uuid == uuid::Uuid::new() {
+        return Err("type must not be UNUSED".into());
+    }
+    Ok(())
+}
+
+// free ranges within the usable range, excluding entry `skip`
+fn get_free_except(gpt: &gpt::Gpt, skip: Option<usize>) -> Vec<(u64, u64)> {
+    let mut used = vec![];
+    for (i, x) in gpt.ent.iter().enumerate() {
+        if Some(i) != skip && !x.is_unused() {
+            used.push((x.ent_lba_start, x.ent_lba_end));
+        }
+    }
+    used.sort_unstable();
+
+    let mut v = vec![];
+    let mut lba = gpt.hdr.hdr_lba_start;
+    for (start, end) in used {
+        if start > lba {
+            v.push((lba, start - 1));
+        }
+        lba = lba.max(end.saturating_add(1));
+    }
+    if lba <= gpt.hdr.hdr_lba_end {
+        v.push((lba, gpt.hdr.hdr_lba_end));
+    }
+    v
+}
+
+pub(crate) fn get_free(gpt: &gpt::Gpt) -> Vec<(u64, u64)> {
+    get_free_except(gpt, None)
+}
+
+pub(crate) fn add_entry(gpt: &mut gpt::Gpt, opt: &Opt) -> Result<usize> {
+    let i = if let Some(i) = opt.part {
+        check_index(gpt, i)?;
+        if !gpt.ent[i].is_unused() {
+            return Err(format!("entry {i} in use").into());
+        }
+        i
+    } else {
+        match gpt.ent.iter().position(gpt::GptEnt::is_unused) {
+            Some(v) => v,
+            None => return Err("no unused entry".into()),
+        }
+    };
+
+    let free = get_free(gpt);
+    if opt.size == Some(0) {
+        return Err("zero size".into());
+    }
+    let start = if let Some(v) = opt.start {
+        v
+    } else {
+        match free
+            .iter()
+            .map(|x| (align_up(x.0, opt.align), x.1))
+            .find(|x| {
+                x.0 <= x.1
+                    && opt
+                        .size
+                        .is_none_or(|v| get_size_end(gpt, x.0, v, opt.align) <= x.1)
+            }) {
+            Some(v) => v.0,
+            None => return Err("no free space".into()),
+        }
+    };
+    let end = match (opt.end, opt.size) {
+        (Some(_), Some(_)) => return Err("--end and --size are exclusive".into()),
+        (Some(v), None) => v,
+        (None, Some(v)) => get_size_end(gpt, start, v, opt.align),
+        (None, None) => match free.iter().find(|x| x.0 <= start && start <= x.1) {
+            Some(v) => v.1,
+            None => return Err(format!("no free space at {start}").into()),
+        },
+    };
+    check_range(gpt, start, end, None)?;
+
+    let mut ent = gpt::GptEnt::new();
+    ent.ent_type = match opt.ptype {
+        Some(v) => v,
+        None => subr::known_str_to_uuid("LINUX_DATA").unwrap(),
+    };
+    check_type(&ent.ent_type)?;
+    ent.ent_uuid = match opt.part_guid {
+        Some(v) => v,
+        None => new_guid(opt, &format!("part/{i}"))?,
+    };
+    check_uuid(gpt, &ent.ent_uuid, None)?;
+    ent.ent_lba_start = start;
+    ent.ent_lba_end = end;
+    ent.ent_attr = opt.attr.unwrap_or(0);
+    if let Some(v) = &opt.name {
+        ent.ent_name = gpt::str_to_ent_name(v)?;
+    }
+    gpt.ent[i] = ent;
+    Ok(i)
+}
+
+pub(crate) fn delete_entry(gpt: &mut gpt::Gpt, opt: &Opt) -> Result<usize> {
+    let i = get_used_index(gpt, opt)?;
+    gpt.ent[i] = gpt::GptEnt::new();
+    Ok(i)
+}
+
+pub(crate) fn modify_entry(gpt: &mut gpt::Gpt, opt: &Opt) -> Result<usize> {
+    let i = get_used_index(gpt, opt)?;
+    let mut ent = gpt.ent[i];
+    if let Some(v) = opt.ptype {
+        check_type(&v)?;
+        ent.ent_type = v;
+    }
+    if let Some(v) = opt.part_guid {
+        check_uuid(gpt, &v, Some(i))?;
+        ent.ent_uuid = v;
+    }
+    if let Some(v) = &opt.name {
+        ent.ent_name = gpt::str_to_ent_name(v)?;
+    }
+    if let Some(v) = opt.attr {
+        ent.ent_attr = v;
+    }
+    if ent == gpt.ent[i] {
+        return Err(format!("nothing to modify for entry {i}").into());
+    }
+    gpt.ent[i] = ent;
+    Ok(i)
+}
+
+pub(crate) fn resize_entry(gpt: &mut gpt::Gpt, opt: &Opt) -> Result<usize> {
+    let i = get_used_index(gpt, opt)?;
+    let start = gpt.ent[i].ent_lba_start;
+    let last = gpt.hdr.hdr_lba_end;
+    // the LBA after the entry stays aligned, except at the end of usable range
+    let end = match (opt.end, opt.size, opt.max) {
+        (Some(v), None, false) => {
+            if v != last && v.saturating_add(1) % opt.align != 0 {
+                return Err(format!(
+                    "end {v} not aligned to {} sectors (next LBA must be aligned)",
+                    opt.align
+                )
+                .into());
+            }
+            v
+        }
+        (None, Some(0), false) => return Err("zero size".into()),
+        (None, Some(v), false) => get_size_end(gpt, start, v, opt.align),
+        (None, None, true) => {
+            let free = get_free_except(gpt, Some(i));
+            let Some(x) = free.iter().find(|x| x.0 <= start && start <= x.1) else {
+                return Err(format!("no free space at {start}").into());
+            };
+            // keep the next LBA aligned if possible
+            let end = (x.1 + 1) / opt.align * opt.align;
+            if end > start {
+                end - 1
+            } else {
+                x.1
+            }
+        }
+        _ => return Err("need one of --end, --size or --max".into()),
+    };
+    check_range(gpt, start, end, Some(i))?;
+    gpt.ent[i].ent_lba_end = end;
+    Ok(i)
+}
+
+pub(crate) fn move_entry(gpt: &mut gpt::Gpt, opt: &Opt) -> Result<usize> {
+    let i = get_used_index(gpt, opt)?;
+    let Some(start) = opt.start else {
+        return Err("start not specified (--start)".into());
+    };
+    if start % opt.align != 0 {
+        return Err(format!("start {start} not aligned to {} sectors", opt.align).into());
+    }
+    let Some(n) = gpt.ent[i].get_sectors() else {
+        return Err(format!(
+            "entry {i} end {} before start {}",
+            gpt.ent[i].ent_lba_end, gpt.ent[i].ent_lba_start
+        )
+        .into());
+    };
+    let end = start.saturating_add(n - 1);
+    check_range(gpt, start, end, Some(i))?;
+    gpt.ent[i].ent_lba_start = start;
+    gpt.ent[i].ent_lba_end = end;
+    Ok(i)
+}
+
+// reorder used entries by start LBA and pack them to the front,
+// returns (old, new) index pairs of used entries
+pub(crate) fn sort_entries(gpt: &mut gpt::Gpt) -> Vec<(usize, usize)> {
+    let mut v: Vec<usize> = (0..gpt.ent.len())
+        .filter(|i| !gpt.ent[*i].is_unused())
+        .collect();
+    v.sort_by_key(|i| gpt.ent[*i].ent_lba_start); // stable
+
+    let old = gpt.ent.clone();
+    gpt.ent.fill(gpt::GptEnt::new());
+    let mut m = vec![];
+    for (new, i) in v.into_iter().enumerate() {
+        gpt.ent[new] = old[i];
+        m.push((i, new));
+    }
+    m
+}
+
+// returns (index, old, new) tuples, where the disk GUID has no index
+pub(crate) fn randomize_guids(
+    gpt: &mut gpt::Gpt,
+    opt: &Opt,
+) -> Result<Vec<(Option<usize>, uuid::Uuid, uuid::Uuid)>> {
+    let v = match &opt.index {
+        Some(v) => {
+            for i in v {
+                check_index(gpt, *i)?;
+                if gpt.ent[*i].is_unused() {
+                    return Err(format!("entry {i} unused").into());
+                }
+            }
+            // each entry gets one new GUID even if listed more than once
+            let mut v = v.clone();
+            v.sort_unstable();
+            v.dedup();
+            v
+        }
+        None => (0..gpt.ent.len())
+            .filter(|i| !gpt.ent[*i].is_unused())
+            .collect(),
+    };
+
+    let mut m = vec![];
+    let u = uuid::uuid_random()?;
+    m.push((None, gpt.hdr.hdr_uuid, u));
+    gpt.hdr.hdr_uuid = u;
+    for i in v {
+        let u = uuid::uuid_random()?;
+        m.push((Some(i), gpt.ent[i].ent_uuid, u));
+        gpt.ent[i].ent_uuid = u;
+    }
+    Ok(m)
+}
+
+// copy data of entry `old` to the location of entry `new`
+pub(crate) fn move_data(
+    fp: &mut std::fs::File,
+    sector_size: u64,
+    old: &gpt::GptEnt,
+    new: &gpt::GptEnt,
+) -> Result<()> {
+    // entries read from disk may be malformed, so check before any I/O
+    let get = || {
+        let total = old.get_sectors()?.checked_mul(sector_size)?;
+        let src = old.ent_lba_start.checked_mul(sector_size)?;
+        let dst = new.ent_lba_start.checked_mul(sector_size)?;
+        src.checked_add(total)?;
+        dst.checked_add(total)?;
+        Some((total, src, dst))
+    };
+    let Some((total, src, dst)) = get() else {
+        return Err(format!(
+            "invalid move of {}-{} to {}",
+            old.ent_lba_start, old.ent_lba_end, new.ent_lba_start
+        )
+        .into());
+    };
+    let mut buf = vec![0; usize::try_from(total.min(1 << 20))?];
+    let mut done = 0;
+
+    while done < total {
+        let n = (total - done).min(u64::try_from(buf.len())?);
+        // copy backward when moving forward so that unread data is not overwritten
+        let offset = if dst > src { total - done - n } else { done };
+        let b = &mut buf[..usize::try_from(n)?];
+        fp.seek(std::io::SeekFrom::Start(src + offset))?;
+        fp.read_exact(b)?;
+        fp.seek(std::io::SeekFrom::Start(dst + offset))?;
+        fp.write_all(b)?;
+        done += n;
+        eprint!("\rmoving data {}% ({done}/{total})", done * 100 / total);
+    }
+    eprintln!();
+    fp.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::gpt;
+    use crate::uuid;
+    use crate::Opt;
+
+    pub(crate) fn get_opt() -> Opt {
+        Opt {
+            sector_size: 512,
+            entries: gpt::GPT_ENTRIES,
+            align: 2048,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn create_image(name: &str, size: u64) -> (std::path::PathBuf, std::fs::File) {
+        let f =
+            std::env::temp_dir().join(format!("dumpgpt-rs-test-{}-{name}.img", std::process::id()));
+        let fp = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&f)
+            .unwrap();
+        fp.set_len(size).unwrap();
+        (f, fp)
+    }
+
+    #[test]
+    fn test_seed() {
+        let mut opt = get_opt();
+        opt.seed = Some("image-1".to_string());
+        opt.size = Some(1 << 20);
+        let mut a = super::init_gpt(64 << 20, &opt).unwrap();
+        let mut b = super::init_gpt(64 << 20, &opt).unwrap();
+        assert_eq!(
+            a.hdr.hdr_uuid,
+            uuid::uuid_v5(&uuid::NAMESPACE_SEED, b"image-1/disk")
+        );
+        assert_eq!(a.hdr.hdr_uuid, b.hdr.hdr_uuid);
+        for g in [&mut a, &mut b] {
+            for _ in 0..2 {
+                super::add_entry(g, &opt).unwrap();
+            }
+        }
+        assert_eq!(a.ent, b.ent);
+        assert_eq!(
+            a.ent[1].ent_uuid,
+            uuid::uuid_v5(&uuid::NAMESPACE_SEED, b"image-1/part/1")
+        );
+        assert_ne!(a.ent[0].ent_uuid, a.ent[1].ent_uuid);
+
+        opt.seed = Some("image-2".to_string());
+        let c = super::init_gpt(64 << 20, &opt).unwrap();
+        assert_ne!(a.hdr.hdr_uuid, c.hdr.hdr_uuid);
+    }
+
+    #[test]
+    fn test_create_gpt() {
+        let (f, mut fp) = create_image("create", 1 << 20);
+        let mut opt = get_opt();
+        opt.disk_guid =
+            Some(uuid::uuid_from_string("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap());
+        super::create_gpt(&mut fp, &opt).unwrap();
+
+        let g = gpt::read_gpt(&mut fp, 512).unwrap();
+        assert_eq!(g.hdr.hdr_lba_self, 1);
+        assert_eq!(g.hdr.hdr_lba_alt, 2047);
+        assert_eq!(g.hdr.hdr_lba_start, 34);
+        assert_eq!(g.hdr.hdr_lba_end, 2014);
+        assert_eq!(g.hdr.hdr_lba_table, 2);
+        assert_eq!(g.hdr.hdr_uuid, opt.disk_guid.unwrap());
+        assert_eq!(g.ent.len(), 128);
+        assert!(g.ent.iter().all(|x| *x == gpt::GptEnt::new()));
+        std::fs::remove_file(f).unwrap();
+    }
+
+    #[test]
+    fn test_create_gpt_4k() {
+        let (f, mut fp) = create_image("create-4k", 1 << 20);
+        let mut opt = get_opt();
+        opt.sector_size = 4096;
+        super::create_gpt(&mut fp, &opt).unwrap();
+
+        let g = gpt::read_gpt(&mut fp, 4096).unwrap();
+        assert_eq!(g.hdr.hdr_lba_alt, 255);
+        assert_eq!(g.hdr.hdr_lba_start, 6);
+        assert_eq!(g.hdr.hdr_lba_end, 250);
+        assert!(gpt::read_gpt(&mut fp, 512).is_err());
+        std::fs::remove_file(f).unwrap();
+    }
+
+    #[test]
+    fn test_create_gpt_invalid() {
+        let (f, mut fp) = create_image("create-invalid", 1 << 20);
+        let mut opt = get_opt();
+        opt.entries = 3;
+        assert!(super::create_gpt(&mut fp, &opt).is_err());
+        opt.entries = 0;
+        assert!(super::create_gpt(&mut fp, &opt).is_err());
+        std::fs::remove_file(f).unwrap();
+
+        let (f, mut fp) = create_image("create-small", 512 * 60);
+        assert!(super::create_gpt(&mut fp, &get_opt()).is_err());
+        std::fs::remove_file(f).unwrap();
+    }
+
+    // 64MiB image, usable range 34-131038
+    // in memory, so that tests running in parallel do not share an image
+    pub(crate) fn new_gpt() -> gpt::Gpt {
+        super::init_gpt(64 << 20, &get_opt()).unwrap()
+    }
+
+    #[test]
+    fn test_add_entry() {
+        let mut g = new_gpt();
+        let mut opt = get_opt();
+        opt.size = Some(1 << 20);
+        opt.ptype = Some(crate::subr::parse_uuid("EFI").unwrap());
+        opt.name = Some("esp".to_string());
+        assert_eq!(super::add_entry(&mut g, &opt).unwrap(), 0);
+        assert_eq!(g.ent[0].ent_lba_start, 2048);
+        assert_eq!(g.ent[0].ent_lba_end, 4095);
+        assert_eq!(g.ent[0].ent_type, opt.ptype.unwrap());
+        assert_eq!(g.ent[0].ent_name[..4], [0x65, 0x73, 0x70, 0]);
+
+        // rest of the disk
+        let mut opt = get_opt();
+        opt.part = Some(3);
+        assert_eq!(super::add_entry(&mut g, &opt).unwrap(), 3);
+        assert_eq!(g.ent[3].ent_lba_start, 4096);
+        assert_eq!(g.ent[3].ent_lba_end, 131_038);
+        assert_ne!(g.ent[0].ent_uuid, g.ent[3].ent_uuid);
+
+        // no space left, except the unaligned head
+        assert!(super::add_entry(&mut g, &get_opt()).is_err());
+        let mut opt = get_opt();
+        opt.start = Some(34);
+        assert_eq!(super::add_entry(&mut g, &opt).unwrap(), 1);
+        assert_eq!(g.ent[1].ent_lba_end, 2047);
+
+        // size rounded up so that the next LBA stays aligned, as resize does
+        let mut g = new_gpt();
+        let mut opt = get_opt();
+        opt.size = Some(3 << 19);
+        super::add_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[0].ent_lba_start, 2048);
+        assert_eq!(g.ent[0].ent_lba_end, 6143);
+        opt.start = Some(126_976); // except at the end of usable range
+        super::add_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[1].ent_lba_end, 130_047);
+    }
+
+    #[test]
+    fn test_add_entry_invalid() {
+        let mut g = new_gpt();
+        let mut opt = get_opt();
+        opt.start = Some(2048);
+        opt.end = Some(4095);
+        super::add_entry(&mut g, &opt).unwrap();
+
+        let mut opt = get_opt();
+        opt.start = Some(4095); // overlap
+        opt.end = Some(8191);
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.start = Some(4096);
+        opt.end = Some(131_039); // beyond lba_end
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.end = Some(4000); // start > end
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.end = None;
+        opt.size = Some(0);
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.size = Some(4096);
+        opt.end = Some(8191);
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.end = None;
+        opt.part = Some(0); // in use
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.part = Some(128);
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.part = None;
+        opt.part_guid = Some(g.ent[0].ent_uuid); // duplicate
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.part_guid = None;
+        opt.ptype = Some(uuid::Uuid::new());
+        assert!(super::add_entry(&mut g, &opt).is_err());
+        opt.ptype = None;
+        super::add_entry(&mut g, &opt).unwrap();
+    }
+
+    #[test]
+    fn test_delete_entry() {
+        let mut g = new_gpt();
+        super::add_entry(&mut g, &get_opt()).unwrap();
+        let mut opt = get_opt();
+        assert!(super::delete_entry(&mut g, &opt).is_err());
+        opt.part = Some(1);
+        assert!(super::delete_entry(&mut g, &opt).is_err());
+        opt.part = Some(0);
+        assert_eq!(super::delete_entry(&mut g, &opt).unwrap(), 0);
+        assert_eq!(g.ent[0], gpt::GptEnt::new());
+        assert!(super::delete_entry(&mut g, &opt).is_err());
+
+        g.ent.clear();
+        assert_eq!(
+            super::check_index(&g, 0).unwrap_err().to_string(),
+            "no entries"
+        );
+        let g = new_gpt();
+        assert_eq!(
+            super::check_index(&g, 128).unwrap_err().to_string(),
+            "entry 128 out of range (0-127)"
+        );
+    }
+
+    #[test]
+    fn test_modify_entry() {
+        let mut g = new_gpt();
+        let mut opt = get_opt();
+        opt.size = Some(1 << 20);
+        super::add_entry(&mut g, &opt).unwrap();
+        super::add_entry(&mut g, &opt).unwrap();
+        let old = g.ent[1];
+
+        let mut opt = get_opt();
+        opt.part = Some(1);
+        assert!(super::modify_entry(&mut g, &opt).is_err());
+        opt.ptype = Some(crate::subr::parse_uuid("LINUX_SWAP").unwrap());
+        opt.name = Some("swap".to_string());
+        opt.attr = Some(1);
+        assert_eq!(super::modify_entry(&mut g, &opt).unwrap(), 1);
+        assert_eq!(g.ent[1].ent_type, opt.ptype.unwrap());
+        assert_eq!(g.ent[1].ent_uuid, old.ent_uuid);
+        assert_eq!(g.ent[1].ent_lba_start, old.ent_lba_start);
+        assert_eq!(g.ent[1].ent_attr, 1);
+
+        let mut opt = get_opt();
+        opt.part = Some(1);
+        opt.part_guid = Some(g.ent[0].ent_uuid);
+        assert!(super::modify_entry(&mut g, &opt).is_err());
+        opt.part_guid = Some(uuid::uuid_random().unwrap());
+        super::modify_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[1].ent_uuid, opt.part_guid.unwrap());
+        opt.part = Some(2);
+        assert!(super::modify_entry(&mut g, &opt).is_err());
+    }
+
+    #[test]
+    fn test_get_free() {
+        let mut g = new_gpt();
+        assert_eq!(super::get_free(&g), [(34, 131_038)]);
+        let mut opt = get_opt();
+        opt.start = Some(4096);
+        opt.end = Some(8191);
+        super::add_entry(&mut g, &opt).unwrap();
+        opt.start = Some(8192);
+        opt.end = Some(131_038);
+        super::add_entry(&mut g, &opt).unwrap();
+        assert_eq!(super::get_free(&g), [(34, 4095)]);
+    }
+
+    #[test]
+    fn test_resize_entry() {
+        let mut g = new_gpt();
+        let mut opt = get_opt();
+        opt.start = Some(2048);
+        opt.end = Some(4095);
+        super::add_entry(&mut g, &opt).unwrap();
+        opt.start = Some(65536);
+        opt.end = Some(131_038);
+        super::add_entry(&mut g, &opt).unwrap();
+
+        let mut opt = get_opt();
+        opt.part = Some(0);
+        assert!(super::resize_entry(&mut g, &opt).is_err());
+        opt.size = Some(2 << 20);
+        assert_eq!(super::resize_entry(&mut g, &opt).unwrap(), 0);
+        assert_eq!(g.ent[0].ent_lba_end, 6143);
+        opt.size = None;
+        opt.size = Some(1 << 20); // rounded up to alignment
+        super::resize_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[0].ent_lba_end, 4095);
+        opt.size = Some(3 << 19);
+        super::resize_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[0].ent_lba_end, 6143);
+        opt.size = None;
+        opt.end = Some(67583); // overlap
+        assert!(super::resize_entry(&mut g, &opt).is_err());
+        opt.end = Some(2047);
+        assert!(super::resize_entry(&mut g, &opt).is_err());
+        opt.end = Some(5000);
+        assert!(super::resize_entry(&mut g, &opt).is_err());
+        opt.end = Some(8191);
+        super::resize_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[0].ent_lba_end, 8191);
+        opt.end = None;
+        opt.max = true;
+        super::resize_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[0].ent_lba_end, 65535);
+
+        // unaligned end of the usable range
+        opt.part = Some(1);
+        opt.max = true;
+        super::resize_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[1].ent_lba_end, 129_023);
+        opt.align = 1;
+        super::resize_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[1].ent_lba_end, 131_038);
+        opt.max = false;
+        opt.align = 2048;
+        opt.end = Some(131_038);
+        super::resize_entry(&mut g, &opt).unwrap();
+        opt.end = None;
+        opt.size = Some((131_038 - 65536 + 1) * 512);
+        super::resize_entry(&mut g, &opt).unwrap();
+        assert_eq!(g.ent[1].ent_lba_end, 131_038);
+    }
+
+    #[test]
+    fn test_move_entry() {
+        let mut g = new_gpt();
+        let mut opt = get_opt();
+        opt.start = Some(2048);
+        opt.end = Some(4095);
+        super::add_entry(&mut g, &opt).unwrap();
+        opt.start = Some(8192);
+        opt.end = Some(10239);
+        super::add_entry(&mut g, &opt).unwrap();
+
+        let mut opt = get_opt();
+        opt.part = Some(0);
+        assert!(super::move_entry(&mut g, &opt).is_err());
+        opt.start = Some(8192); // overlap
+        assert!(super::move_entry(&mut g, &opt).is_err());
+        opt.start = Some(4097); // unaligned
+        assert!(super::move_entry(&mut g, &opt).is_err());
+        opt.start = Some(4096);
+        assert_eq!(super::move_entry(&mut g, &opt).unwrap(), 0);
+        assert_eq!(g.ent[0].ent_lba_start, 4096);
+        assert_eq!(g.ent[0].ent_lba_end, 6143);
+        opt.part = Some(1);
+        opt.start = Some(130_048); // beyond lba_end
+        assert!(super::move_entry(&mut g, &opt).is_err());
+
+        // malformed entry read from disk
+        g.ent[1].ent_lba_start = 50000;
+        g.ent[1].ent_lba_end = 40000;
+        opt.start = Some(8192);
+        let e = super::move_entry(&mut g, &opt).unwrap_err();
+        assert_eq!(e.to_string(), "entry 1 end 40000 before start 50000");
+    }
+
+    #[test]
+    fn test_move_data() {
+        use std::io::Read;
+        use std::io::Seek;
+        use std::io::Write;
+
+        let (f, mut fp) = create_image("move-data", 8 << 20);
+        let mut old = gpt::GptEnt::new();
+        old.ent_lba_start = 2048;
+        old.ent_lba_end = 2048 + 4095; // 2MiB
+        let data: Vec<u8> = (0..2 << 20).map(|x: u32| (x % 251) as u8).collect();
+        fp.seek(std::io::SeekFrom::Start(2048 * 512)).unwrap();
+        fp.write_all(&data).unwrap();
+
+        // forward with overlap, then backward with overlap
+        for start in [4096, 3072] {
+            let mut new = old;
+            new.ent_lba_start = start;
+            new.ent_lba_end = start + 4095;
+            super::move_data(&mut fp, 512, &old, &new).unwrap();
+            let mut buf = vec![0; data.len()];
+            fp.seek(std::io::SeekFrom::Start(start * 512)).unwrap();
+            fp.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, data);
+            old = new;
+        }
+
+        let mut new = old;
+        old.ent_lba_end = old.ent_lba_start - 1;
+        assert!(super::move_data(&mut fp, 512, &old, &new).is_err());
+        old.ent_lba_end = u64::MAX / 512;
+        assert!(super::move_data(&mut fp, 512, &old, &new).is_err());
+        new.ent_lba_start = u64::MAX;
+        assert!(super::move_data(&mut fp, 512, &new, &new).is_err());
+        std::fs::remove_file(f).unwrap();
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let mut g = new_gpt();
+        for (i, start) in [(5, 8192), (2, 2048), (7, 4096)] {
+            let mut opt = get_opt();
+            opt.part = Some(i);
+            opt.start = Some(start);
+            opt.size = Some(1 << 20);
+            super::add_entry(&mut g, &opt).unwrap();
+        }
+        let old = g.ent.clone();
+        assert_eq!(super::sort_entries(&mut g), [(2, 0), (7, 1), (5, 2)]);
+        assert_eq!(g.ent[0], old[2]);
+        assert_eq!(g.ent[1], old[7]);
+        assert_eq!(g.ent[2], old[5]);
+        assert!(g.ent[3..].iter().all(gpt::GptEnt::is_unused));
+        assert_eq!(g.ent.len(), old.len());
+
+        // already sorted
+        assert_eq!(super::sort_entries(&mut g), [(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn test_randomize_guids() {
+        let mut g = new_gpt();
+        for _ in 0..3 {
+            let mut opt = get_opt();
+            opt.size = Some(1 << 20);
+            super::add_entry(&mut g, &opt).unwrap();
+        }
+        let old = g.clone();
+
+        let m = super::randomize_guids(&mut g, &get_opt()).unwrap();
+        assert_eq!(m.len(), 4);
+        assert_eq!(m[0], (None, old.hdr.hdr_uuid, g.hdr.hdr_uuid));
+        assert_ne!(g.hdr.hdr_uuid, old.hdr.hdr_uuid);
+        for (i, x) in m[1..].iter().enumerate() {
+            assert_eq!(*x, (Some(i), old.ent[i].ent_uuid, g.ent[i].ent_uuid));
+            assert_ne!(g.ent[i].ent_uuid, old.ent[i].ent_uuid);
+            assert_eq!(g.ent[i].ent_lba_start, old.ent[i].ent_lba_start);
+        }
+
+        let old = g.clone();
+        let mut opt = get_opt();
+        opt.index = Some(vec![2]);
+        let m = super::randomize_guids(&mut g, &opt).unwrap();
+        assert_eq!(m.len(), 2);
+        assert_ne!(g.hdr.hdr_uuid, old.hdr.hdr_uuid);
+        assert_eq!(g.ent[0], old.ent[0]);
+        assert_eq!(g.ent[1], old.ent[1]);
+        assert_ne!(g.ent[2].ent_uuid, old.ent[2].ent_uuid);
+
+        let old = g.clone();
+        opt.index = Some(vec![1, 0, 1, 0]);
+        let m = super::randomize_guids(&mut g, &opt).unwrap();
+        assert_eq!(m.len(), 3);
+        assert_eq!(m[1], (Some(0), old.ent[0].ent_uuid, g.ent[0].ent_uuid));
+        assert_eq!(m[2], (Some(1), old.ent[1].ent_uuid, g.ent[1].ent_uuid));
+        assert_eq!(g.ent[2], old.ent[2]);
+        opt.index = Some(vec![3]);
+        assert!(super::randomize_guids(&mut g, &opt).is_err());
+    }
+}
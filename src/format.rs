@@ -0,0 +1,349 @@
+use crate::gpt;
+use crate::subr;
+use crate::Result;
+use std::fmt::Write;
+
+// e.g. /dev/sda -> /dev/sda1, /dev/nvme0n1 -> /dev/nvme0n1p1
+pub(crate) fn get_part_path(device: &str, i: usize) -> String {
+    if device.ends_with(|c: char| c.is_ascii_digit()) {
+        format!("{device}p{}", i + 1)
+    } else {
+        format!("{device}{}", i + 1)
+    }
+}
+
+fn get_sectors(i: usize, x: &gpt::GptEnt) -> Result<u64> {
+    match x.get_sectors() {
+        Some(v) => Ok(v),
+        None => Err(format!(
+            "entry {i} end {} before start {}",
+            x.ent_lba_end, x.ent_lba_start
+        )
+        .into()),
+    }
+}
+
+fn get_bytes(i: usize, x: &gpt::GptEnt, sector_size: u64) -> Result<u64> {
+    match get_sectors(i, x)?.checked_mul(sector_size) {
+        Some(v) => Ok(v),
+        None => Err(format!("entry {i} size overflow").into()),
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub(crate) fn sfdisk_to_string(device: &str, gpt: &gpt::Gpt) -> Result<String> {
+    let mut s = String::new();
+    let hdr = &gpt.hdr;
+    let uuid = |u| subr::uuid_to_str(u).to_uppercase();
+    writeln!(s, "label: gpt").unwrap();
+    writeln!(s, "label-id: {}", uuid(&hdr.hdr_uuid)).unwrap();
+    writeln!(s, "device: {device}").unwrap();
+    writeln!(s, "unit: sectors").unwrap();
+    writeln!(s, "first-lba: {}", hdr.hdr_lba_start).unwrap();
+    writeln!(s, "last-lba: {}", hdr.hdr_lba_end).unwrap();
+    if hdr.hdr_entries != gpt::GPT_ENTRIES {
+        writeln!(s, "table-length: {}", hdr.hdr_entries).unwrap();
+    }
+    writeln!(s, "sector-size: {}", gpt.sector_size).unwrap();
+    writeln!(s).unwrap();
+
+    for (i, x) in gpt.ent.iter().enumerate() {
+        if x.is_unused() {
+            continue;
+        }
+        write!(
+            s,
+            "{} : start={:>12}, size={:>12}, type={}, uuid={}",
+            get_part_path(device, i),
+            x.ent_lba_start,
+            get_sectors(i, x)?,
+            uuid(&x.ent_type),
+            uuid(&x.ent_uuid),
+        )
+        .unwrap();
+        let name = gpt::ent_name_to_string(&x.ent_name);
+        if !name.is_empty() {
+            write!(s, ", name={}", quote(&name)).unwrap();
+        }
+        let attrs = subr::attr_to_names(x.ent_attr, true);
+        if !attrs.is_empty() {
+            write!(s, ", attrs={}", quote(&attrs.join(" "))).unwrap();
+        }
+        writeln!(s).unwrap();
+    }
+    Ok(s)
+}
+
+// RFC 4180 quoting for csv, tabs and newlines replaced for tsv
+fn table_field(s: &str, sep: char) -> String {
+    if sep != ',' {
+        s.replace(['\t', '\n', '\r'], " ")
+    } else if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+pub(crate) const TABLE_COLUMNS: [&str; 10] = [
+    "device",
+    "index",
+    "type",
+    "type_name",
+    "uuid",
+    "start",
+    "end",
+    "size",
+    "attrs",
+    "name",
+];
+
+// one row per used entry, size is in bytes
+pub(crate) fn table_to_string(device: &str, gpt: &gpt::Gpt, sep: char) -> Result<String> {
+    let mut s = String::new();
+    for (i, x) in gpt.ent.iter().enumerate() {
+        if x.is_unused() {
+            continue;
+        }
+        let row = [
+            device.to_string(),
+            i.to_string(),
+            subr::uuid_to_str(&x.ent_type),
+            subr::known_uuid_to_str(&x.ent_type).to_string(),
+            subr::uuid_to_str(&x.ent_uuid),
+            x.ent_lba_start.to_string(),
+            x.ent_lba_end.to_string(),
+            get_bytes(i, x, gpt.sector_size)?.to_string(),
+            subr::attr_to_names(x.ent_attr, false).join(" "),
+            gpt::ent_name_to_string(&x.ent_name),
+        ];
+        let row: Vec<_> = row.iter().map(|x| table_field(x, sep)).collect();
+        writeln!(s, "{}", row.join(&sep.to_string())).unwrap();
+    }
+    Ok(s)
+}
+
+// single quote unless only characters safe for the shell are used
+fn shell_quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-+.,/:@%".contains(c))
+    {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+// similar to blkid -o export, entries are indexed as in dump
+pub(crate) fn export_to_string(device: &str, gpt: &gpt::Gpt) -> Result<String> {
+    let mut s = String::new();
+    let hdr = &gpt.hdr;
+    let mut kv = |k: &str, v: &str| writeln!(s, "GPT_{k}={}", shell_quote(v)).unwrap();
+    kv("DEVICE", device);
+    kv("DISK_UUID", &subr::uuid_to_str(&hdr.hdr_uuid));
+    kv("SECTOR_SIZE", &gpt.sector_size.to_string());
+    kv("FIRST_LBA", &hdr.hdr_lba_start.to_string());
+    kv("LAST_LBA", &hdr.hdr_lba_end.to_string());
+    kv("ENTRIES", &hdr.hdr_entries.to_string());
+    kv(
+        "PART_COUNT",
+        &gpt.ent
+            .iter()
+            .filter(|x| !x.is_unused())
+            .count()
+            .to_string(),
+    );
+    for (i, x) in gpt.ent.iter().enumerate() {
+        if x.is_unused() {
+            continue;
+        }
+        let size = get_bytes(i, x, gpt.sector_size)?;
+        // numbered from 1 like blkid and /dev/sdXN
+        let n = i + 1;
+        kv(&format!("PART_{n}_TYPE"), &subr::uuid_to_str(&x.ent_type));
+        kv(
+            &format!("PART_{n}_TYPE_NAME"),
+            subr::known_uuid_to_str(&x.ent_type),
+        );
+        kv(&format!("PART_{n}_UUID"), &subr::uuid_to_str(&x.ent_uuid));
+        kv(&format!("PART_{n}_START"), &x.ent_lba_start.to_string());
+        kv(&format!("PART_{n}_END"), &x.ent_lba_end.to_string());
+        kv(&format!("PART_{n}_SIZE"), &size.to_string());
+        kv(
+            &format!("PART_{n}_ATTRS"),
+            &format!("0x{:016x}", x.ent_attr),
+        );
+        kv(
+            &format!("PART_{n}_NAME"),
+            &gpt::ent_name_to_string(&x.ent_name),
+        );
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit;
+    use crate::gpt;
+    use crate::subr;
+    use crate::uuid;
+
+    #[test]
+    fn test_get_part_path() {
+        assert_eq!(super::get_part_path("/dev/sda", 0), "/dev/sda1");
+        assert_eq!(super::get_part_path("/dev/nvme0n1", 1), "/dev/nvme0n1p2");
+        assert_eq!(super::get_part_path("disk.img", 9), "disk.img10");
+    }
+
+    #[test]
+    fn test_sfdisk_to_string() {
+        let mut g = edit::tests::new_gpt();
+        g.hdr.hdr_uuid = uuid::uuid_from_string("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap();
+        let mut opt = edit::tests::get_opt();
+        opt.size = Some(1 << 20);
+        opt.ptype = Some(subr::parse_uuid("EFI").unwrap());
+        opt.part_guid =
+            Some(uuid::uuid_from_string("c12a7328-f81f-11d2-ba4b-00a0c93ec93b").unwrap());
+        opt.name = Some("EFI \"system\"".to_string());
+        opt.attr = Some(1 | 1 << 60);
+        edit::add_entry(&mut g, &opt).unwrap();
+        let mut opt = edit::tests::get_opt();
+        opt.part = Some(2);
+        opt.part_guid =
+            Some(uuid::uuid_from_string("0fc63daf-8483-4772-8e79-3d69d8477de4").unwrap());
+        edit::add_entry(&mut g, &opt).unwrap();
+
+        assert_eq!(
+            super::sfdisk_to_string("/dev/sda", &g).unwrap(),
+            "label: gpt
+label-id: 516E7CB4-6ECF-11D6-8FF8-00022D09712B
+device: /dev/sda
+unit: sectors
+first-lba: 34
+last-lba: 131038
+sector-size: 512
+
+/dev/sda1 : start=        2048, size=        2048, type=C12A7328-F81F-11D2-BA4B-00A0C93EC93B, uuid=C12A7328-F81F-11D2-BA4B-00A0C93EC93B, name=\"EFI \\\"system\\\"\", attrs=\"RequiredPartition GUID:60\"
+/dev/sda3 : start=        4096, size=      126943, type=0FC63DAF-8483-4772-8E79-3D69D8477DE4, uuid=0FC63DAF-8483-4772-8E79-3D69D8477DE4
+"
+        );
+
+        g.hdr.hdr_entries = 256;
+        g.ent.resize(256, gpt::GptEnt::new());
+        assert!(super::sfdisk_to_string("/dev/sda", &g)
+            .unwrap()
+            .contains("\ntable-length: 256\n"));
+    }
+
+    #[test]
+    fn test_table_field() {
+        assert_eq!(super::table_field("a b", ','), "a b");
+        assert_eq!(super::table_field("a,\"b\"", ','), "\"a,\"\"b\"\"\"");
+        assert_eq!(super::table_field("a,\"b\"", '\t'), "a,\"b\"");
+        assert_eq!(super::table_field("a\tb\n", '\t'), "a b ");
+    }
+
+    #[test]
+    fn test_table_to_string() {
+        let mut g = edit::tests::new_gpt();
+        let mut opt = edit::tests::get_opt();
+        opt.size = Some(1 << 20);
+        opt.ptype = Some(subr::parse_uuid("EFI").unwrap());
+        opt.part_guid =
+            Some(uuid::uuid_from_string("c12a7328-f81f-11d2-ba4b-00a0c93ec93b").unwrap());
+        opt.name = Some("EFI, \"system\"".to_string());
+        opt.attr = Some(1 | 4);
+        edit::add_entry(&mut g, &opt).unwrap();
+        let mut opt = edit::tests::get_opt();
+        opt.part = Some(2);
+        opt.ptype = Some(uuid::uuid_from_string("01234567-89ab-cdef-0123-456789abcdef").unwrap());
+        opt.part_guid =
+            Some(uuid::uuid_from_string("0fc63daf-8483-4772-8e79-3d69d8477de4").unwrap());
+        opt.size = Some(2 << 20);
+        edit::add_entry(&mut g, &opt).unwrap();
+
+        assert_eq!(
+            super::table_to_string("/dev/sda", &g, ',').unwrap(),
+            "/dev/sda,0,c12a7328-f81f-11d2-ba4b-00a0c93ec93b,EFI,c12a7328-f81f-11d2-ba4b-00a0c93ec93b,2048,4095,1048576,required legacy-bios-bootable,\"EFI, \"\"system\"\"\"
+/dev/sda,2,01234567-89ab-cdef-0123-456789abcdef,,0fc63daf-8483-4772-8e79-3d69d8477de4,4096,8191,2097152,,
+"
+        );
+        assert_eq!(
+            super::table_to_string("/dev/sda", &g, '\t').unwrap().lines().next().unwrap(),
+            "/dev/sda\t0\tc12a7328-f81f-11d2-ba4b-00a0c93ec93b\tEFI\tc12a7328-f81f-11d2-ba4b-00a0c93ec93b\t2048\t4095\t1048576\trequired legacy-bios-bootable\tEFI, \"system\""
+        );
+
+        // reserved bits have no name but must not be dropped
+        g.ent[2].ent_attr = 1 | 1 << 10 | 1 << 60;
+        assert_eq!(
+            super::table_to_string("/dev/sda", &g, ',').unwrap().lines().nth(1).unwrap(),
+            "/dev/sda,2,01234567-89ab-cdef-0123-456789abcdef,,0fc63daf-8483-4772-8e79-3d69d8477de4,4096,8191,2097152,required guid:60 0x400,"
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(super::shell_quote("abc-1.2"), "abc-1.2");
+        assert_eq!(super::shell_quote(""), "''");
+        assert_eq!(super::shell_quote("a b"), "'a b'");
+        assert_eq!(super::shell_quote("it's $x"), r"'it'\''s $x'");
+    }
+
+    #[test]
+    fn test_export_to_string() {
+        let mut g = edit::tests::new_gpt();
+        g.hdr.hdr_uuid = uuid::uuid_from_string("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap();
+        let mut opt = edit::tests::get_opt();
+        opt.part = Some(3);
+        opt.size = Some(1 << 20);
+        opt.ptype = Some(subr::parse_uuid("EFI").unwrap());
+        opt.part_guid =
+            Some(uuid::uuid_from_string("c12a7328-f81f-11d2-ba4b-00a0c93ec93b").unwrap());
+        opt.name = Some("EFI 'system'".to_string());
+        opt.attr = Some(1);
+        edit::add_entry(&mut g, &opt).unwrap();
+
+        assert_eq!(
+            super::export_to_string("/dev/sda", &g).unwrap(),
+            r"GPT_DEVICE=/dev/sda
+GPT_DISK_UUID=516e7cb4-6ecf-11d6-8ff8-00022d09712b
+GPT_SECTOR_SIZE=512
+GPT_FIRST_LBA=34
+GPT_LAST_LBA=131038
+GPT_ENTRIES=128
+GPT_PART_COUNT=1
+GPT_PART_4_TYPE=c12a7328-f81f-11d2-ba4b-00a0c93ec93b
+GPT_PART_4_TYPE_NAME=EFI
+GPT_PART_4_UUID=c12a7328-f81f-11d2-ba4b-00a0c93ec93b
+GPT_PART_4_START=2048
+GPT_PART_4_END=4095
+GPT_PART_4_SIZE=1048576
+GPT_PART_4_ATTRS=0x0000000000000001
+GPT_PART_4_NAME='EFI '\''system'\'''
+"
+        );
+    }
+
+    #[test]
+    fn test_malformed_entry() {
+        let mut g = edit::tests::new_gpt();
+        edit::add_entry(&mut g, &edit::tests::get_opt()).unwrap();
+        g.ent[0].ent_lba_start = 4096;
+        g.ent[0].ent_lba_end = 2048;
+        let e = "entry 0 end 2048 before start 4096";
+        let err = |r: crate::Result<String>| r.unwrap_err().to_string();
+        assert_eq!(err(super::sfdisk_to_string("/dev/sda", &g)), e);
+        assert_eq!(err(super::table_to_string("/dev/sda", &g, ',')), e);
+        assert_eq!(err(super::export_to_string("/dev/sda", &g)), e);
+        g.ent[0].ent_lba_start = 0;
+        g.ent[0].ent_lba_end = u64::MAX - 1;
+        assert_eq!(
+            err(super::table_to_string("/dev/sda", &g, ',')),
+            "entry 0 size overflow"
+        );
+    }
+}
@@ -4,10 +4,14 @@ use crate::Opt;
 use crate::Result;
 use std::io::Read;
 use std::io::Seek;
+use std::io::Write;
 
-const UNIT_SIZE: usize = 512;
+pub(crate) const GPT_SIG: [u8; 8] = *b"EFI PART";
+pub(crate) const GPT_REVISION: u32 = 0x0001_0000;
+pub(crate) const GPT_HDR_SIZE: u32 = 92;
+pub(crate) const GPT_ENT_SIZE: u32 = 128;
+pub(crate) const GPT_ENTRIES: u32 = 128;
 
-#[repr(C)]
 #[derive(Clone, Copy, Debug, Default)]
 pub(crate) struct GptHdr {
     pub(crate) hdr_sig: [u8; 8],
@@ -24,7 +28,6 @@ pub(crate) struct GptHdr {
     pub(crate) hdr_entries: u32,
     pub(crate) hdr_entsz: u32,
     pub(crate) hdr_crc_table: u32,
-    pub(crate) padding: u32,
 }
 
 impl GptHdr {
@@ -35,8 +38,7 @@ impl GptHdr {
     }
 }
 
-#[repr(C)]
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) struct GptEnt {
     pub(crate) ent_type: uuid::Uuid,
     pub(crate) ent_uuid: uuid::Uuid,
@@ -65,9 +67,330 @@ impl GptEnt {
             ..Default::default()
         }
     }
+
+    pub(crate) fn is_unused(&self) -> bool {
+        self.ent_type == uuid::Uuid::new()
+    }
+
+    // None if the table is malformed with end before start
+    pub(crate) fn get_sectors(&self) -> Option<u64> {
+        self.ent_lba_end
+            .checked_sub(self.ent_lba_start)
+            .and_then(|x| x.checked_add(1))
+    }
+}
+
+pub(crate) fn str_to_ent_name(s: &str) -> Result<[u16; 36]> {
+    let mut name = [0; 36];
+    for (i, x) in s.encode_utf16().enumerate() {
+        if i >= name.len() {
+            return Err(format!("name \"{s}\" too long").into());
+        }
+        name[i] = x;
+    }
+    Ok(name)
+}
+
+pub(crate) fn ent_name_to_string(name: &[u16; 36]) -> String {
+    let n = name.iter().position(|x| *x == 0).unwrap_or(name.len());
+    String::from_utf16_lossy(&name[..n])
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Gpt {
+    pub(crate) hdr: GptHdr, // primary
+    pub(crate) ent: Vec<GptEnt>,
+    pub(crate) sector_size: u64,
+}
+
+pub(crate) fn read_at(fp: &mut std::fs::File, offset: u64, size: usize) -> Result<Vec<u8>> {
+    let mut buf = alloc_buffer(size);
+    fp.seek(std::io::SeekFrom::Start(offset))?;
+    fp.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+// raw device bytes, either read from the file or sliced out of a read-only
+// memory map, which avoids a system call per read when scanning many images
+pub(crate) enum Source<'a> {
+    File(&'a mut std::fs::File),
+    Mmap(memmap2::Mmap),
+}
+
+impl<'a> Source<'a> {
+    pub(crate) fn new(fp: &'a mut std::fs::File, mmap: bool) -> Result<Self> {
+        if !mmap {
+            return Ok(Self::File(fp));
+        }
+        // length is given explicitly since metadata of block devices is 0
+        let size = usize::try_from(subr::get_size(fp)?)?;
+        // SAFETY: the map is only read, the image being truncated by another
+        // process while mapped would fault rather than read stale data
+        let m = unsafe { memmap2::MmapOptions::new().len(size).map(&*fp)? };
+        Ok(Self::Mmap(m))
+    }
+
+    pub(crate) fn read_at(
+        &mut self,
+        offset: u64,
+        size: usize,
+    ) -> Result<std::borrow::Cow<'_, [u8]>> {
+        match self {
+            Self::File(fp) => Ok(std::borrow::Cow::Owned(read_at(fp, offset, size)?)),
+            Self::Mmap(m) => {
+                let start = usize::try_from(offset)?;
+                match start.checked_add(size).and_then(|end| m.get(start..end)) {
+                    Some(v) => Ok(std::borrow::Cow::Borrowed(v)),
+                    None => Err(Box::new(std::io::Error::from(
+                        std::io::ErrorKind::UnexpectedEof,
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn write_at(fp: &mut std::fs::File, offset: u64, buf: &[u8]) -> Result<()> {
+    fp.seek(std::io::SeekFrom::Start(offset))?;
+    fp.write_all(buf)?;
+    Ok(())
+}
+
+// LBAs read from disk may be garbage, so the byte offset is checked
+pub(crate) fn get_offset(lba: u64, sector_size: u64) -> Result<u64> {
+    match lba.checked_mul(sector_size) {
+        Some(v) => Ok(v),
+        None => Err(format!("LBA {lba} out of range").into()),
+    }
+}
+
+pub(crate) fn get_table_sectors(hdr: &GptHdr, sector_size: u64) -> u64 {
+    let size = u64::from(hdr.hdr_entries) * u64::from(hdr.hdr_entsz);
+    size.div_ceil(sector_size)
 }
 
-fn try_known_uuid_to_str(uuid: &uuid::Uuid, opt: &Opt) -> String {
+// entries may be garbage if the header is broken, so limit what is read,
+// the size is rounded up to whole sectors
+pub(crate) fn get_table_bytes(hdr: &GptHdr, sector_size: u64) -> Result<usize> {
+    if hdr.hdr_entsz != GPT_ENT_SIZE {
+        return Err(format!("unsupported entry size {}", hdr.hdr_entsz).into());
+    }
+    if hdr.hdr_entries > 1 << 16 {
+        return Err(format!("too many entries {}", hdr.hdr_entries).into());
+    }
+    match get_table_sectors(hdr, sector_size).checked_mul(sector_size) {
+        Some(v) => Ok(usize::try_from(v)?),
+        None => Err(format!("table size overflow with sector size {sector_size}").into()),
+    }
+}
+
+pub(crate) fn get_header_crc(hdr: &GptHdr) -> u32 {
+    let mut h = *hdr;
+    h.hdr_crc_self = 0;
+    subr::crc32(&encode_header(&h))
+}
+
+pub(crate) fn get_table_crc(buf: &[u8], hdr: &GptHdr) -> Result<u32> {
+    let n = usize::try_from(hdr.hdr_entries)? * usize::try_from(hdr.hdr_entsz)?;
+    Ok(subr::crc32(&buf[..n]))
+}
+
+pub(crate) fn get_alt_header(hdr: &GptHdr, sector_size: u64) -> GptHdr {
+    let mut h = *hdr;
+    h.hdr_lba_self = hdr.hdr_lba_alt;
+    h.hdr_lba_alt = hdr.hdr_lba_self;
+    h.hdr_lba_table = hdr.hdr_lba_alt - get_table_sectors(hdr, sector_size);
+    h.hdr_crc_self = get_header_crc(&h);
+    h
+}
+
+// callers check length first, so these never panic
+fn get_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn get_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+fn get_uuid(buf: &[u8], offset: usize) -> uuid::Uuid {
+    uuid::uuid_from_le_bytes(buf[offset..offset + 16].try_into().unwrap())
+}
+
+pub(crate) fn parse_header(buf: &[u8]) -> Result<GptHdr> {
+    if buf.len() < GPT_HDR_SIZE as usize {
+        return Err(format!("header truncated to {} bytes", buf.len()).into());
+    }
+    Ok(GptHdr {
+        hdr_sig: buf[..8].try_into()?,
+        hdr_revision: get_u32(buf, 8),
+        hdr_size: get_u32(buf, 12),
+        hdr_crc_self: get_u32(buf, 16),
+        reserved: get_u32(buf, 20),
+        hdr_lba_self: get_u64(buf, 24),
+        hdr_lba_alt: get_u64(buf, 32),
+        hdr_lba_start: get_u64(buf, 40),
+        hdr_lba_end: get_u64(buf, 48),
+        hdr_uuid: get_uuid(buf, 56),
+        hdr_lba_table: get_u64(buf, 72),
+        hdr_entries: get_u32(buf, 80),
+        hdr_entsz: get_u32(buf, 84),
+        hdr_crc_table: get_u32(buf, 88),
+    })
+}
+
+pub(crate) fn encode_header(hdr: &GptHdr) -> [u8; GPT_HDR_SIZE as usize] {
+    let mut buf = [0; GPT_HDR_SIZE as usize];
+    buf[..8].copy_from_slice(&hdr.hdr_sig);
+    buf[8..12].copy_from_slice(&hdr.hdr_revision.to_le_bytes());
+    buf[12..16].copy_from_slice(&hdr.hdr_size.to_le_bytes());
+    buf[16..20].copy_from_slice(&hdr.hdr_crc_self.to_le_bytes());
+    buf[20..24].copy_from_slice(&hdr.reserved.to_le_bytes());
+    buf[24..32].copy_from_slice(&hdr.hdr_lba_self.to_le_bytes());
+    buf[32..40].copy_from_slice(&hdr.hdr_lba_alt.to_le_bytes());
+    buf[40..48].copy_from_slice(&hdr.hdr_lba_start.to_le_bytes());
+    buf[48..56].copy_from_slice(&hdr.hdr_lba_end.to_le_bytes());
+    buf[56..72].copy_from_slice(&uuid::uuid_to_le_bytes(&hdr.hdr_uuid));
+    buf[72..80].copy_from_slice(&hdr.hdr_lba_table.to_le_bytes());
+    buf[80..84].copy_from_slice(&hdr.hdr_entries.to_le_bytes());
+    buf[84..88].copy_from_slice(&hdr.hdr_entsz.to_le_bytes());
+    buf[88..92].copy_from_slice(&hdr.hdr_crc_table.to_le_bytes());
+    buf
+}
+
+pub(crate) fn parse_entry(buf: &[u8]) -> Result<GptEnt> {
+    if buf.len() < GPT_ENT_SIZE as usize {
+        return Err(format!("entry truncated to {} bytes", buf.len()).into());
+    }
+    let mut ent_name = [0; 36];
+    for (i, x) in ent_name.iter_mut().enumerate() {
+        *x = u16::from_le_bytes([buf[56 + 2 * i], buf[57 + 2 * i]]);
+    }
+    Ok(GptEnt {
+        ent_type: get_uuid(buf, 0),
+        ent_uuid: get_uuid(buf, 16),
+        ent_lba_start: get_u64(buf, 32),
+        ent_lba_end: get_u64(buf, 40),
+        ent_attr: get_u64(buf, 48),
+        ent_name,
+    })
+}
+
+pub(crate) fn encode_entry(ent: &GptEnt) -> [u8; GPT_ENT_SIZE as usize] {
+    let mut buf = [0; GPT_ENT_SIZE as usize];
+    buf[..16].copy_from_slice(&uuid::uuid_to_le_bytes(&ent.ent_type));
+    buf[16..32].copy_from_slice(&uuid::uuid_to_le_bytes(&ent.ent_uuid));
+    buf[32..40].copy_from_slice(&ent.ent_lba_start.to_le_bytes());
+    buf[40..48].copy_from_slice(&ent.ent_lba_end.to_le_bytes());
+    buf[48..56].copy_from_slice(&ent.ent_attr.to_le_bytes());
+    for (i, x) in ent.ent_name.iter().enumerate() {
+        buf[56 + 2 * i..58 + 2 * i].copy_from_slice(&x.to_le_bytes());
+    }
+    buf
+}
+
+pub(crate) fn check_header(hdr: &GptHdr) -> Result<()> {
+    if hdr.hdr_sig != GPT_SIG {
+        return Err("invalid GPT signature".into());
+    }
+    if hdr.hdr_size != GPT_HDR_SIZE {
+        return Err(format!("unsupported header size {}", hdr.hdr_size).into());
+    }
+    if hdr.hdr_crc_self != get_header_crc(hdr) {
+        return Err("header CRC mismatch".into());
+    }
+    // entry size and count only, the size itself is not needed here
+    get_table_bytes(hdr, 512)?;
+    Ok(())
+}
+
+pub(crate) fn parse_entries(buf: &[u8], hdr: &GptHdr) -> Result<Vec<GptEnt>> {
+    let entsz = usize::try_from(hdr.hdr_entsz)?;
+    let mut v = vec![];
+    for i in 0..usize::try_from(hdr.hdr_entries)? {
+        let Some(x) = buf.get(entsz * i..entsz * (i + 1)) else {
+            return Err(format!("entries truncated at entry {i}").into());
+        };
+        v.push(parse_entry(x)?);
+    }
+    Ok(v)
+}
+
+pub(crate) fn read_header(
+    fp: &mut std::fs::File,
+    hdr_lba: u64,
+    sector_size: u64,
+) -> Result<GptHdr> {
+    let offset = get_offset(hdr_lba, sector_size)?;
+    let buf = read_at(fp, offset, usize::try_from(sector_size)?)?;
+    parse_header(&buf)
+}
+
+pub(crate) fn read_table(
+    fp: &mut std::fs::File,
+    hdr: &GptHdr,
+    sector_size: u64,
+) -> Result<Vec<u8>> {
+    let n = get_table_bytes(hdr, sector_size)?;
+    read_at(fp, get_offset(hdr.hdr_lba_table, sector_size)?, n)
+}
+
+pub(crate) fn read_gpt_at(fp: &mut std::fs::File, hdr_lba: u64, sector_size: u64) -> Result<Gpt> {
+    let hdr = read_header(fp, hdr_lba, sector_size)?;
+    check_header(&hdr)?;
+    let buf = read_table(fp, &hdr, sector_size)?;
+    if hdr.hdr_crc_table != get_table_crc(&buf, &hdr)? {
+        return Err("entries CRC mismatch".into());
+    }
+    Ok(Gpt {
+        hdr,
+        ent: parse_entries(&buf, &hdr)?,
+        sector_size,
+    })
+}
+
+pub(crate) fn read_gpt(fp: &mut std::fs::File, sector_size: u64) -> Result<Gpt> {
+    read_gpt_at(fp, 1, sector_size)
+}
+
+pub(crate) fn write_gpt(fp: &mut std::fs::File, gpt: &mut Gpt) -> Result<()> {
+    let ss = gpt.sector_size;
+    let mut tbl = vec![];
+    for x in &gpt.ent {
+        tbl.extend_from_slice(&encode_entry(x));
+    }
+    assert_eq!(gpt.ent.len(), usize::try_from(gpt.hdr.hdr_entries)?);
+    gpt.hdr.hdr_crc_table = subr::crc32(&tbl);
+    gpt.hdr.hdr_crc_self = get_header_crc(&gpt.hdr);
+    tbl.resize(get_table_bytes(&gpt.hdr, ss)?, 0);
+    let hdr2 = get_alt_header(&gpt.hdr, ss);
+
+    // secondary first, so that an interrupted write leaves primary intact
+    for hdr in [&hdr2, &gpt.hdr] {
+        let mut buf = alloc_buffer(usize::try_from(ss)?);
+        buf[..GPT_HDR_SIZE as usize].copy_from_slice(&encode_header(hdr));
+        write_at(fp, hdr.hdr_lba_table * ss, &tbl)?;
+        write_at(fp, hdr.hdr_lba_self * ss, &buf)?;
+    }
+    fp.sync_all()?;
+    Ok(())
+}
+
+pub(crate) fn write_pmbr(fp: &mut std::fs::File, gpt: &Gpt) -> Result<()> {
+    let mut buf = alloc_buffer(512);
+    let p = &mut buf[446..462];
+    p[1..4].copy_from_slice(&[0x00, 0x02, 0x00]); // CHS of LBA 1
+    p[4] = 0xee;
+    p[5..8].copy_from_slice(&[0xff, 0xff, 0xff]);
+    p[8..12].copy_from_slice(&1u32.to_le_bytes());
+    let n = u32::try_from(gpt.hdr.hdr_lba_alt).unwrap_or(u32::MAX);
+    p[12..16].copy_from_slice(&n.to_le_bytes());
+    buf[510] = 0x55;
+    buf[511] = 0xaa;
+    write_at(fp, 0, &buf)
+}
+
+pub(crate) fn try_known_uuid_to_str(uuid: &uuid::Uuid, opt: &Opt) -> String {
     if opt.symbol {
         let s = subr::known_uuid_to_str(uuid);
         if !s.is_empty() {
@@ -77,22 +400,37 @@ fn try_known_uuid_to_str(uuid: &uuid::Uuid, opt: &Opt) -> String {
     subr::uuid_to_str(uuid)
 }
 
-fn alloc_buffer() -> Vec<u8> {
-    let buf = vec![0; UNIT_SIZE];
-    assert_eq!(buf.len(), UNIT_SIZE);
+// dump filters, entries match if all given filters match
+pub(crate) fn match_entry(i: usize, ent: &GptEnt, opt: &Opt) -> bool {
+    opt.ptype.is_none_or(|x| x == ent.ent_type)
+        && opt.attr.is_none_or(|x| ent.ent_attr & x == x)
+        && opt.index.as_ref().is_none_or(|x| x.contains(&i))
+        && opt
+            .name_match
+            .as_ref()
+            .is_none_or(|x| x.is_match(&ent_name_to_string(&ent.ent_name)))
+}
+
+// clear entries not matching dump filters
+pub(crate) fn filter_entries(gpt: &mut Gpt, opt: &Opt) {
+    for (i, x) in gpt.ent.iter_mut().enumerate() {
+        if !match_entry(i, x, opt) {
+            *x = GptEnt::new();
+        }
+    }
+}
+
+fn alloc_buffer(size: usize) -> Vec<u8> {
+    let buf = vec![0; size];
+    assert_eq!(buf.len(), size);
     assert_eq!(buf.len() % 512, 0);
     buf
 }
 
-fn dump_header(fp: &mut std::fs::File, hdr_lba: u64, opt: &Opt) -> Result<GptHdr> {
-    let mut buf = alloc_buffer();
-    let hdr_offset = hdr_lba * u64::try_from(buf.len())?;
-    fp.seek(std::io::SeekFrom::Start(hdr_offset))?;
-    fp.read_exact(&mut buf)?;
-
-    let ret = unsafe { buf.align_to::<GptHdr>() };
-    assert!(ret.0.is_empty());
-    let hdr = ret.1[0];
+fn dump_header(src: &mut Source, hdr_lba: u64, opt: &Opt) -> Result<GptHdr> {
+    let ss = opt.sector_size;
+    let offset = get_offset(hdr_lba, ss)?;
+    let hdr = parse_header(&src.read_at(offset, usize::try_from(ss)?)?)?;
 
     let mut hdr_sig = [' '; 8];
     for i in 0..hdr.hdr_sig.len() {
@@ -124,6 +462,11 @@ fn dump_header(fp: &mut std::fs::File, hdr_lba: u64, opt: &Opt) -> Result<GptHdr
     println!("lba_end  = 0x{:016x}", hdr.hdr_lba_end);
 
     println!("uuid     = {}", try_known_uuid_to_str(&hdr.hdr_uuid, opt));
+    if opt.verbose {
+        for x in uuid::uuid_describe(&hdr.hdr_uuid) {
+            println!("           {x}");
+        }
+    }
 
     println!("lba_table= 0x{:016x}", hdr.hdr_lba_table);
     println!("entries  = {}", hdr.hdr_entries);
@@ -139,87 +482,314 @@ fn dump_header(fp: &mut std::fs::File, hdr_lba: u64, opt: &Opt) -> Result<GptHdr
     Ok(hdr)
 }
 
-fn dump_entries(fp: &mut std::fs::File, hdr: &GptHdr, opt: &Opt) -> Result<()> {
-    let lba_table_size = usize::try_from(hdr.hdr_entsz)? * usize::try_from(hdr.hdr_entries)?;
-    let lba_table_sectors = lba_table_size / UNIT_SIZE;
-    let mut total = 0;
-
+fn dump_entries(src: &mut Source, hdr: &GptHdr, opt: &Opt) -> Result<()> {
     println!(
         "{:<3} {:<36} {:<36} {:<16} {:<16} {:<16} name",
         "#", "type", "uniq", "lba_start", "lba_end", "attr"
     );
 
-    for i in 0..lba_table_sectors {
-        let mut buf = alloc_buffer();
-        let offset = (hdr.hdr_lba_table + u64::try_from(i)?) * u64::try_from(buf.len())?;
-        fp.seek(std::io::SeekFrom::Start(offset))?;
-        fp.read_exact(&mut buf)?;
-
-        let sector_entries = buf.len() / usize::try_from(hdr.hdr_entsz)?;
+    // whole array in one read, entries are not split on sector boundaries
+    let ss = opt.sector_size;
+    let n = get_table_bytes(hdr, ss)?;
+    let buf = src.read_at(get_offset(hdr.hdr_lba_table, ss)?, n)?;
+    let entsz = usize::try_from(hdr.hdr_entsz)?;
 
-        for j in 0..sector_entries {
-            let p = &buf[std::mem::size_of::<GptEnt>() * j..];
-            let ret = unsafe { p.align_to::<GptEnt>() };
-            assert!(ret.0.is_empty());
-            let p = &ret.1[0];
+    for i in 0..usize::try_from(hdr.hdr_entries)? {
+        let p = &parse_entry(&buf[entsz * i..entsz * (i + 1)])?;
+        if !opt.verbose && *p == GptEnt::new() {
+            continue;
+        }
+        if !match_entry(i, p, opt) {
+            continue;
+        }
 
-            if !opt.verbose && *p == GptEnt::new() {
-                total += 1;
-                continue;
+        let mut name = [0u8; 36];
+        let mut nlen = 0;
+        assert_eq!(p.ent_name.len(), name.len());
+        for (k, v) in name.iter_mut().enumerate() {
+            *v = (p.ent_name[k] & 0xFF).try_into()?; // XXX ascii
+            if *v == 0 {
+                nlen = k;
+                break;
             }
+        }
 
-            let mut name = [0u8; 36];
-            let mut nlen = 0;
-            assert_eq!(p.ent_name.len(), name.len());
-            for (k, v) in name.iter_mut().enumerate() {
-                *v = (p.ent_name[k] & 0xFF).try_into()?; // XXX ascii
-                if *v == 0 {
-                    nlen = k;
-                    break;
+        println!(
+            "{:<3} {:<36} {:<36} {:<016x} {:<016x} {:<016x} {}",
+            i,
+            try_known_uuid_to_str(&p.ent_type, opt),
+            try_known_uuid_to_str(&p.ent_uuid, opt),
+            p.ent_lba_start,
+            p.ent_lba_end,
+            p.ent_attr,
+            std::str::from_utf8(&name[..nlen])?
+        );
+        // decode the type and unique GUIDs under their columns
+        if opt.verbose {
+            for (u, n) in [(&p.ent_type, 4), (&p.ent_uuid, 41)] {
+                if *u == uuid::Uuid::new() {
+                    continue;
+                }
+                for x in uuid::uuid_describe(u) {
+                    println!("{:n$}{x}", "");
                 }
             }
-
-            println!(
-                "{:<3} {:<36} {:<36} {:<016x} {:<016x} {:<016x} {}",
-                i * sector_entries + j,
-                try_known_uuid_to_str(&p.ent_type, opt),
-                try_known_uuid_to_str(&p.ent_uuid, opt),
-                p.ent_lba_start,
-                p.ent_lba_end,
-                p.ent_attr,
-                std::str::from_utf8(&name[..nlen])?
-            );
-            total += 1;
         }
     }
-    assert_eq!(total, hdr.hdr_entries);
     Ok(())
 }
 
 pub(crate) fn dump_gpt(fp: &mut std::fs::File, opt: &Opt) -> Result<()> {
+    let src = &mut Source::new(fp, opt.mmap)?;
     let mut hdr2 = GptHdr::new();
 
     // primary header
     println!("primary header");
-    let hdr1 = dump_header(fp, 1, opt)?;
+    let hdr1 = dump_header(src, 1, opt)?;
 
     // secondary header
     if !opt.noalt {
         println!();
         println!("secondary header");
-        hdr2 = dump_header(fp, hdr1.hdr_lba_alt, opt)?;
+        hdr2 = dump_header(src, hdr1.hdr_lba_alt, opt)?;
     }
 
     // primary entries
     println!();
     println!("primary entries");
-    dump_entries(fp, &hdr1, opt)?;
+    dump_entries(src, &hdr1, opt)?;
 
     // secondary entries
     if !opt.noalt {
         println!();
         println!("secondary entries");
-        dump_entries(fp, &hdr2, opt)?;
+        dump_entries(src, &hdr2, opt)?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::edit;
+    use crate::subr;
+    use crate::uuid;
+
+    // on-disk bytes written out field by field, so that expected values do
+    // not depend on host byte order
+    fn get_header_bytes() -> Vec<u8> {
+        let mut b = vec![];
+        b.extend_from_slice(b"EFI PART");
+        b.extend_from_slice(&0x0001_0000u32.to_le_bytes());
+        b.extend_from_slice(&92u32.to_le_bytes());
+        b.extend_from_slice(&0u32.to_le_bytes()); // crc_self
+        b.extend_from_slice(&0u32.to_le_bytes());
+        for x in [1u64, 0x1_ffff, 34, 0x1_ffde] {
+            b.extend_from_slice(&x.to_le_bytes());
+        }
+        b.extend_from_slice(&[
+            0xb4, 0x7c, 0x6e, 0x51, 0xcf, 0x6e, 0xd6, 0x11, 0x8f, 0xf8, 0x00, 0x02, 0x2d, 0x09,
+            0x71, 0x2b,
+        ]);
+        b.extend_from_slice(&2u64.to_le_bytes());
+        b.extend_from_slice(&128u32.to_le_bytes());
+        b.extend_from_slice(&128u32.to_le_bytes());
+        b.extend_from_slice(&0x1234_5678u32.to_le_bytes());
+        let crc = subr::crc32(&b);
+        b[16..20].copy_from_slice(&crc.to_le_bytes());
+        b.resize(512, 0);
+        b
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let b = get_header_bytes();
+        let h = super::parse_header(&b).unwrap();
+        assert_eq!(h.hdr_sig, super::GPT_SIG);
+        assert_eq!(h.hdr_revision, super::GPT_REVISION);
+        assert_eq!(h.hdr_size, 92);
+        assert_eq!(h.hdr_lba_self, 1);
+        assert_eq!(h.hdr_lba_alt, 0x1_ffff);
+        assert_eq!(h.hdr_lba_start, 34);
+        assert_eq!(h.hdr_lba_end, 0x1_ffde);
+        assert_eq!(
+            subr::uuid_to_str(&h.hdr_uuid),
+            "516e7cb4-6ecf-11d6-8ff8-00022d09712b"
+        );
+        assert_eq!(h.hdr_lba_table, 2);
+        assert_eq!(h.hdr_entries, 128);
+        assert_eq!(h.hdr_entsz, 128);
+        assert_eq!(h.hdr_crc_table, 0x1234_5678);
+        assert_eq!(
+            h.hdr_crc_self,
+            u32::from_le_bytes(b[16..20].try_into().unwrap())
+        );
+        assert_eq!(super::get_header_crc(&h), h.hdr_crc_self);
+        super::check_header(&h).unwrap();
+        assert_eq!(super::encode_header(&h)[..], b[..92]);
+
+        // no alignment requirement on the input
+        let mut x = vec![0];
+        x.extend_from_slice(&b);
+        assert_eq!(
+            super::encode_header(&super::parse_header(&x[1..]).unwrap())[..],
+            b[..92]
+        );
+        assert!(super::parse_header(&b[..91]).is_err());
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let mut b = vec![0; 256];
+        b[128..144].copy_from_slice(&[
+            0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e,
+            0xc9, 0x3b,
+        ]);
+        b[160..168].copy_from_slice(&2048u64.to_le_bytes());
+        b[168..176].copy_from_slice(&0x1000_0fffu64.to_le_bytes());
+        b[176..184].copy_from_slice(&(1u64 << 60 | 1).to_le_bytes());
+        b[184..190].copy_from_slice(&[b'e', 0, 0xe9, 0, 0x3d, 0xd8]);
+        b[190..192].copy_from_slice(&[0x00, 0xde]);
+        let mut h = super::GptHdr::new();
+        h.hdr_entries = 2;
+        h.hdr_entsz = 128;
+        let v = super::parse_entries(&b, &h).unwrap();
+        assert!(v[0].is_unused());
+        assert_eq!(subr::known_uuid_to_str(&v[1].ent_type), "EFI");
+        assert_eq!(v[1].ent_lba_start, 2048);
+        assert_eq!(v[1].ent_lba_end, 0x1000_0fff);
+        assert_eq!(v[1].ent_attr, 1 << 60 | 1);
+        assert_eq!(
+            super::ent_name_to_string(&v[1].ent_name),
+            "e\u{e9}\u{1f600}"
+        );
+        assert_eq!(super::encode_entry(&v[1])[..], b[128..]);
+        assert_eq!(super::encode_entry(&v[0]), [0; 128]);
+
+        assert!(super::parse_entry(&b[..127]).is_err());
+        h.hdr_entries = 3;
+        assert!(super::parse_entries(&b, &h).is_err());
+    }
+
+    #[test]
+    fn test_write_gpt() {
+        let (f, mut fp) = edit::tests::create_image("write", 64 << 20);
+        let mut opt = edit::tests::get_opt();
+        opt.disk_guid =
+            Some(uuid::uuid_from_string("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap());
+        let mut g = edit::create_gpt(&mut fp, &opt).unwrap();
+        opt.ptype = subr::known_str_to_uuid("EFI");
+        opt.start = Some(2048);
+        opt.end = Some(4095);
+        opt.attr = Some(1);
+        edit::add_entry(&mut g, &opt).unwrap();
+        super::write_gpt(&mut fp, &mut g).unwrap();
+
+        let b = super::read_at(&mut fp, 512, 512).unwrap();
+        assert_eq!(&b[..8], b"EFI PART");
+        assert_eq!(b[8..16], [0, 0, 1, 0, 92, 0, 0, 0]);
+        assert_eq!(b[24..32], 1u64.to_le_bytes());
+        assert_eq!(b[32..40], ((64u64 << 11) - 1).to_le_bytes());
+        assert_eq!(b[56..60], [0xb4, 0x7c, 0x6e, 0x51]);
+        assert_eq!(b[64..72], [0x8f, 0xf8, 0x00, 0x02, 0x2d, 0x09, 0x71, 0x2b]);
+        assert!(b[92..].iter().all(|x| *x == 0));
+        let b = super::read_at(&mut fp, 1024, 512).unwrap();
+        assert_eq!(b[..4], [0x28, 0x73, 0x2a, 0xc1]);
+        assert_eq!(b[32..40], 2048u64.to_le_bytes());
+        assert_eq!(b[40..48], 4095u64.to_le_bytes());
+        assert_eq!(b[48..56], 1u64.to_le_bytes());
+
+        let x = super::read_gpt(&mut fp, 512).unwrap();
+        assert_eq!(x.hdr.hdr_crc_self, g.hdr.hdr_crc_self);
+        assert_eq!(x.ent, g.ent);
+        std::fs::remove_file(f).unwrap();
+    }
+
+    #[test]
+    fn test_source() {
+        let (f, mut fp) = edit::tests::create_image("source", 64 << 20);
+        let opt = edit::tests::get_opt();
+        let g = edit::create_gpt(&mut fp, &opt).unwrap();
+        let tbl = super::read_table(&mut fp, &g.hdr, 512).unwrap();
+        for mmap in [false, true] {
+            let mut src = super::Source::new(&mut fp, mmap).unwrap();
+            assert_eq!(&src.read_at(512, 512).unwrap()[..8], b"EFI PART");
+            assert_eq!(src.read_at(1024, tbl.len()).unwrap()[..], tbl[..]);
+            assert!(src.read_at((64 << 20) - 512, 1024).is_err());
+            assert!(src.read_at(u64::MAX, 512).is_err());
+        }
+        for mmap in [false, true] {
+            let mut opt = edit::tests::get_opt();
+            opt.mmap = mmap;
+            super::dump_gpt(&mut fp, &opt).unwrap();
+        }
+
+        // broken entry size must not be trusted for the table read
+        let mut src = super::Source::new(&mut fp, false).unwrap();
+        let opt = edit::tests::get_opt();
+        let mut h = g.hdr;
+        h.hdr_entsz = u32::MAX;
+        let e = super::dump_entries(&mut src, &h, &opt).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!("unsupported entry size {}", u32::MAX)
+        );
+        h.hdr_entsz = super::GPT_ENT_SIZE;
+        h.hdr_entries = u32::MAX;
+        assert!(super::dump_entries(&mut src, &h, &opt).is_err());
+        h.hdr_entries = 128;
+        h.hdr_lba_table = u64::MAX;
+        let e = super::dump_entries(&mut src, &h, &opt).unwrap_err();
+        assert_eq!(e.to_string(), format!("LBA {} out of range", u64::MAX));
+
+        // broken secondary location must not be trusted either
+        let mut h = g.hdr;
+        h.hdr_lba_alt = u64::MAX;
+        super::write_at(&mut fp, 512, &super::encode_header(&h)).unwrap();
+        let e = super::dump_gpt(&mut fp, &opt).unwrap_err();
+        assert_eq!(e.to_string(), format!("LBA {} out of range", u64::MAX));
+        assert!(super::read_header(&mut fp, u64::MAX, 512).is_err());
+        std::fs::remove_file(f).unwrap();
+    }
+
+    #[test]
+    fn test_filter_entries() {
+        let mut g = edit::tests::new_gpt();
+        for (t, name, attr) in [
+            ("EFI", "esp", 1),
+            ("LINUX_DATA", "root-a", 4),
+            ("LINUX_DATA", "root-b", 5),
+            ("LINUX_SWAP", "swap", 0),
+        ] {
+            let mut opt = edit::tests::get_opt();
+            opt.size = Some(1 << 20);
+            opt.ptype = subr::known_str_to_uuid(t);
+            opt.name = Some(name.to_string());
+            opt.attr = Some(attr);
+            edit::add_entry(&mut g, &opt).unwrap();
+        }
+        let get = |opt: &crate::Opt| {
+            let mut x = g.clone();
+            super::filter_entries(&mut x, opt);
+            (0..x.ent.len())
+                .filter(|i| !x.ent[*i].is_unused())
+                .collect::<Vec<_>>()
+        };
+
+        let mut opt = edit::tests::get_opt();
+        assert_eq!(get(&opt), [0, 1, 2, 3]);
+        opt.ptype = subr::known_str_to_uuid("LINUX_DATA");
+        assert_eq!(get(&opt), [1, 2]);
+        opt.attr = Some(1);
+        assert_eq!(get(&opt), [2]);
+        opt.attr = None;
+        opt.name_match = Some(regex::Regex::new("-a$").unwrap());
+        assert_eq!(get(&opt), [1]);
+        opt.ptype = None;
+        opt.name_match = Some(regex::Regex::new("^(esp|swap)").unwrap());
+        assert_eq!(get(&opt), [0, 3]);
+        opt.index = Some(vec![1, 2, 3]);
+        assert_eq!(get(&opt), [3]);
+        opt.name_match = None;
+        assert_eq!(get(&opt), [1, 2, 3]);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::gpt;
+use crate::Opt;
+use crate::Result;
+use std::fmt::Write;
+
+// name, offset, size
+type Field = (&'static str, usize, usize);
+
+const HDR_FIELDS: [Field; 14] = [
+    ("sig", 0, 8),
+    ("revision", 8, 4),
+    ("size", 12, 4),
+    ("crc_self", 16, 4),
+    ("reserved", 20, 4),
+    ("lba_self", 24, 8),
+    ("lba_alt", 32, 8),
+    ("lba_start", 40, 8),
+    ("lba_end", 48, 8),
+    ("uuid", 56, 16),
+    ("lba_table", 72, 8),
+    ("entries", 80, 4),
+    ("entsz", 84, 4),
+    ("crc_table", 88, 4),
+];
+
+const ENT_FIELDS: [Field; 6] = [
+    ("type", 0, 16),
+    ("uniq", 16, 16),
+    ("lba_start", 32, 8),
+    ("lba_end", 40, 8),
+    ("attr", 48, 8),
+    ("name", 56, 72),
+];
+
+// one line per field, fields longer than 16 bytes continue on following
+// lines, and repeated all-zero lines within a field are collapsed into "*"
+fn annotate(s: &mut String, buf: &[u8], offset: u64, fields: &[Field]) -> Result<()> {
+    let mut v = fields.to_vec();
+    let end = fields.last().map_or(0, |x| x.1 + x.2);
+    if buf.len() > end {
+        v.push(("padding", end, buf.len() - end));
+    }
+    for (name, start, size) in v {
+        let mut zero = false;
+        for (i, x) in buf[start..start + size].chunks(16).enumerate() {
+            if i > 0 && x.iter().all(|b| *b == 0) {
+                if !zero {
+                    writeln!(s, "*")?;
+                }
+                zero = true;
+                continue;
+            }
+            zero = false;
+            let hex: Vec<_> = x.iter().map(|b| format!("{b:02x}")).collect();
+            writeln!(
+                s,
+                "{:08x}  {:<47}  {}",
+                offset + u64::try_from(start + 16 * i)?,
+                hex.join(" "),
+                if i == 0 { name } else { "" }
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn dump_header(s: &mut String, fp: &mut std::fs::File, lba: u64, ss: u64) -> Result<gpt::GptHdr> {
+    let offset = gpt::get_offset(lba, ss)?;
+    let buf = gpt::read_at(fp, offset, usize::try_from(ss)?)?;
+    annotate(s, &buf, offset, &HDR_FIELDS)?;
+    gpt::parse_header(&buf)
+}
+
+fn dump_entries(
+    s: &mut String,
+    fp: &mut std::fs::File,
+    hdr: &gpt::GptHdr,
+    opt: &Opt,
+) -> Result<()> {
+    // broken location or size is reported in place of the entries
+    let ss = opt.sector_size;
+    let buf = match gpt::read_table(fp, hdr, ss) {
+        Ok(v) => v,
+        Err(e) => {
+            writeln!(s, "{e}")?;
+            return Ok(());
+        }
+    };
+    let table = gpt::get_offset(hdr.hdr_lba_table, ss)?;
+    let entsz = usize::try_from(hdr.hdr_entsz)?;
+    for i in 0..usize::try_from(hdr.hdr_entries)? {
+        let x = &buf[entsz * i..entsz * (i + 1)];
+        if !opt.verbose && x.iter().all(|b| *b == 0) {
+            continue;
+        }
+        writeln!(s, "entry {i}")?;
+        let offset = table + u64::try_from(entsz * i)?;
+        annotate(s, x, offset, &ENT_FIELDS)?;
+    }
+    Ok(())
+}
+
+// raw sectors read by dump_gpt, without validating headers
+pub(crate) fn hexdump_gpt(fp: &mut std::fs::File, opt: &Opt) -> Result<String> {
+    let ss = opt.sector_size;
+    let mut s = String::new();
+    writeln!(s, "primary header")?;
+    let hdr1 = dump_header(&mut s, fp, 1, ss)?;
+    let mut hdr2 = None;
+    if !opt.noalt {
+        writeln!(s)?;
+        writeln!(s, "secondary header")?;
+        match dump_header(&mut s, fp, hdr1.hdr_lba_alt, ss) {
+            Ok(v) => hdr2 = Some(v),
+            Err(e) => writeln!(s, "{e}")?,
+        }
+    }
+    writeln!(s)?;
+    writeln!(s, "primary entries")?;
+    dump_entries(&mut s, fp, &hdr1, opt)?;
+    if let Some(hdr2) = hdr2 {
+        writeln!(s)?;
+        writeln!(s, "secondary entries")?;
+        dump_entries(&mut s, fp, &hdr2, opt)?;
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edit;
+    use crate::gpt;
+
+    #[test]
+    fn test_annotate() {
+        let mut buf = vec![0; 48];
+        buf[0] = 0xab;
+        buf[40] = 1;
+        let mut s = String::new();
+        super::annotate(&mut s, &buf, 0x400, &[("a", 0, 2), ("b", 2, 38)]).unwrap();
+        assert_eq!(
+            s,
+            "00000400  ab 00                                            a
+00000402  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  b
+*
+00000428  01 00 00 00 00 00 00 00                          padding
+"
+        );
+    }
+
+    #[test]
+    fn test_hexdump_gpt() {
+        let (f, mut fp) = edit::tests::create_image("hexdump", 64 << 20);
+        let mut opt = edit::tests::get_opt();
+        let mut g = edit::create_gpt(&mut fp, &opt).unwrap();
+        opt.part = Some(1);
+        edit::add_entry(&mut g, &opt).unwrap();
+        gpt::write_gpt(&mut fp, &mut g).unwrap();
+
+        let s = super::hexdump_gpt(&mut fp, &opt).unwrap();
+        let v: Vec<_> = s.lines().collect();
+        assert_eq!(v[0], "primary header");
+        assert_eq!(
+            v[1],
+            "00000200  45 46 49 20 50 41 52 54                          sig"
+        );
+        assert_eq!(
+            v[2],
+            "00000208  00 00 01 00                                      revision"
+        );
+        assert!(s.contains("\nsecondary header\n03fffe00  45 46 49 20"));
+        assert!(s.contains("\nentry 1\n00000480  af 3d c6 0f"));
+        assert!(!s.contains("entry 0"));
+        assert_eq!(s.matches("entry 1\n").count(), 2);
+
+        opt.noalt = true;
+        opt.verbose = true;
+        let s = super::hexdump_gpt(&mut fp, &opt).unwrap();
+        assert!(!s.contains("secondary"));
+        assert_eq!(s.matches("\nentry ").count(), 128);
+
+        // broken header is still dumped
+        gpt::write_at(&mut fp, 512 + 84, &[0; 4]).unwrap();
+        let s = super::hexdump_gpt(&mut fp, &opt).unwrap();
+        assert!(s.contains("unsupported entry size 0"));
+
+        // so are broken secondary and table locations
+        let mut h = g.hdr;
+        h.hdr_lba_alt = u64::MAX;
+        h.hdr_lba_table = u64::MAX;
+        gpt::write_at(&mut fp, 512, &gpt::encode_header(&h)).unwrap();
+        opt.noalt = false;
+        let s = super::hexdump_gpt(&mut fp, &opt).unwrap();
+        let e = format!("LBA {} out of range", u64::MAX);
+        assert!(s.contains(&format!("\nsecondary header\n{e}\n")));
+        assert!(s.contains(&format!("\nprimary entries\n{e}\n")));
+        assert!(!s.contains("secondary entries"));
+        std::fs::remove_file(f).unwrap();
+    }
+}
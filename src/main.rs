@@ -1,4 +1,12 @@
+mod backup;
+mod detail;
+mod diff;
+mod edit;
+mod format;
 mod gpt;
+mod hexdump;
+mod map;
+mod spec;
 mod subr;
 mod uuid;
 
@@ -6,13 +14,101 @@ const VERSION: [i32; 3] = [0, 1, 8];
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 struct Opt {
     verbose: bool,
     symbol: bool,
     noalt: bool,
+    sector_size: u64,
+    entries: u32,
+    disk_guid: Option<uuid::Uuid>,
+    part: Option<usize>,
+    start: Option<u64>,
+    end: Option<u64>,
+    size: Option<u64>,
+    ptype: Option<uuid::Uuid>,
+    part_guid: Option<uuid::Uuid>,
+    name: Option<String>,
+    attr: Option<u64>,
+    align: u64,
+    max: bool,
+    move_data: bool,
+    index: Option<Vec<usize>>,
+    mapping: bool,
+    format: String,
+    map: bool,
+    map_width: usize,
+    legend: bool,
+    entry: Option<usize>,
+    hexdump: bool,
+    name_match: Option<regex::Regex>,
+    seed: Option<String>,
+    namespace: Option<uuid::Uuid>,
+    mmap: bool,
 }
 
+// name, arguments, description
+const COMMANDS: [(&str, &str, &str); 14] = [
+    (
+        "create",
+        "<path>",
+        "Create an empty GPT (--entries, --disk-guid, --seed)",
+    ),
+    (
+        "add",
+        "<path>",
+        "Add an entry (--part, --start, --end or --size, --type, --part-guid, --name, --attr, --seed)",
+    ),
+    ("delete", "<path>", "Delete an entry (--part)"),
+    (
+        "modify",
+        "<path>",
+        "Modify an entry (--part, --type, --part-guid, --name, --attr)",
+    ),
+    (
+        "resize",
+        "<path>",
+        "Resize an entry (--part, --end or --size or --max)",
+    ),
+    (
+        "move",
+        "<path>",
+        "Move an entry (--part, --start, --move-data)",
+    ),
+    ("sort", "<path>", "Sort entries by start LBA and pack them"),
+    (
+        "randomize-guids",
+        "<path>",
+        "Randomize disk and entry GUIDs (--index, --mapping)",
+    ),
+    ("backup", "<path> <file>", "Save GPT metadata to a file"),
+    (
+        "restore",
+        "<file> <path>",
+        "Restore GPT metadata from a file",
+    ),
+    (
+        "diff",
+        "<path> <path>",
+        "Compare two GPTs (--format text|json)",
+    ),
+    (
+        "import",
+        "<spec> <path>",
+        "Create a GPT from an sfdisk script or TOML/JSON spec (--seed)",
+    ),
+    (
+        "types",
+        "[<pattern>]",
+        "List known types (--format text|json), or resolve a symbol to its GUID",
+    ),
+    (
+        "uuidgen",
+        "[<name>]",
+        "Generate a random GUID, or a name-based GUID of name (--namespace)",
+    ),
+];
+
 fn get_version_string() -> String {
     format!("{}.{}.{}", VERSION[0], VERSION[1], VERSION[2])
 }
@@ -24,25 +120,342 @@ fn print_version() {
 fn usage(progname: &str, opts: &getopts::Options) {
     print!(
         "{}",
-        opts.usage(&format!("usage: {progname} [<options>] <paths>"))
+        opts.usage(&format!(
+            "usage: {progname} [<options>] [<command>] <paths>"
+        ))
     );
+    println!();
+    println!("Commands:");
+    for (name, args, desc) in &COMMANDS {
+        println!("    {:<24}{desc}", format!("{name} {args}"));
+    }
 }
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let progname = &args[0];
+fn open_device(path: &str, write: bool) -> std::fs::File {
+    match std::fs::OpenOptions::new()
+        .read(true)
+        .write(write)
+        .open(path)
+    {
+        Ok(v) => v,
+        Err(e) => {
+            println!("{path}: {e}");
+            std::process::exit(1);
+        }
+    }
+}
 
-    subr::assert_ds();
+fn read_device(path: &str, opt: &Opt) -> gpt::Gpt {
+    let mut fp = open_device(path, false);
+    match gpt::read_gpt(&mut fp, opt.sector_size) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("{path}: {e}");
+            std::process::exit(1);
+        }
+    }
+}
 
-    if !subr::is_le() {
-        println!("big-endian arch unsupported");
-        std::process::exit(1);
+fn get_opt_value<T>(
+    matches: &getopts::Matches,
+    name: &str,
+    default: T,
+    f: fn(&str) -> Result<T>,
+) -> T {
+    match matches.opt_str(name) {
+        Some(v) => match f(&v) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("--{name}: {e}");
+                std::process::exit(1);
+            }
+        },
+        None => default,
+    }
+}
+
+fn edit_device(
+    path: &str,
+    opt: &Opt,
+    f: fn(&mut gpt::Gpt, &Opt) -> Result<usize>,
+) -> Result<gpt::GptEnt> {
+    let mut fp = open_device(path, true);
+    let mut gpt = gpt::read_gpt(&mut fp, opt.sector_size)?;
+    let i = f(&mut gpt, opt)?;
+    let ent = gpt.ent[i];
+    gpt::write_gpt(&mut fp, &mut gpt)?;
+    if ent.is_unused() {
+        println!("{path}: entry {i} deleted");
+    } else {
+        println!(
+            "{path}: entry {i} {}-{}",
+            ent.ent_lba_start, ent.ent_lba_end
+        );
     }
+    Ok(ent)
+}
+
+fn run_command(args: &[String], opt: &Opt) -> Result<()> {
+    match args[0].as_str() {
+        "create" => {
+            let mut fp = open_device(&args[1], true);
+            let gpt = edit::create_gpt(&mut fp, opt)?;
+            println!(
+                "{}: GPT {} with {} entries",
+                args[1],
+                subr::uuid_to_str(&gpt.hdr.hdr_uuid),
+                gpt.hdr.hdr_entries
+            );
+        }
+        "add" => _ = edit_device(&args[1], opt, edit::add_entry)?,
+        "delete" => _ = edit_device(&args[1], opt, edit::delete_entry)?,
+        "modify" => _ = edit_device(&args[1], opt, edit::modify_entry)?,
+        "resize" => _ = edit_device(&args[1], opt, edit::resize_entry)?,
+        "move" => {
+            let mut fp = open_device(&args[1], true);
+            let mut gpt = gpt::read_gpt(&mut fp, opt.sector_size)?;
+            let old = gpt.ent.clone();
+            let i = edit::move_entry(&mut gpt, opt)?;
+            if opt.move_data {
+                edit::move_data(&mut fp, gpt.sector_size, &old[i], &gpt.ent[i])?;
+            }
+            let ent = gpt.ent[i];
+            gpt::write_gpt(&mut fp, &mut gpt)?;
+            println!(
+                "{}: entry {i} {}-{}",
+                args[1], ent.ent_lba_start, ent.ent_lba_end
+            );
+        }
+        "sort" => {
+            let mut fp = open_device(&args[1], true);
+            let mut gpt = gpt::read_gpt(&mut fp, opt.sector_size)?;
+            let m = edit::sort_entries(&mut gpt);
+            if m.iter().all(|x| x.0 == x.1) {
+                println!("{}: already sorted", args[1]);
+                return Ok(());
+            }
+            gpt::write_gpt(&mut fp, &mut gpt)?;
+            // partition numbers as in /dev/sdXN are 1-based
+            for (old, new) in m {
+                let s = if old == new { "" } else { " (changed)" };
+                println!(
+                    "{}: entry {old} -> {new}, partition {} -> {}{s}",
+                    args[1],
+                    old + 1,
+                    new + 1
+                );
+            }
+        }
+        "randomize-guids" => {
+            let mut fp = open_device(&args[1], true);
+            let mut gpt = gpt::read_gpt(&mut fp, opt.sector_size)?;
+            let m = edit::randomize_guids(&mut gpt, opt)?;
+            gpt::write_gpt(&mut fp, &mut gpt)?;
+            if opt.mapping {
+                for (i, old, new) in m {
+                    let i = i.map_or("disk".to_string(), |i| i.to_string());
+                    println!(
+                        "{i} {} {}",
+                        subr::uuid_to_str(&old),
+                        subr::uuid_to_str(&new)
+                    );
+                }
+            } else {
+                println!("{}: {} GUIDs randomized", args[1], m.len());
+            }
+        }
+        "backup" => {
+            let mut fp = open_device(&args[1], false);
+            let b = backup::read_backup(&mut fp, opt.sector_size)?;
+            std::fs::write(&args[2], backup::backup_to_bytes(&b))?;
+            println!("{}: saved to {}", args[1], args[2]);
+        }
+        "restore" => {
+            let b = backup::backup_from_bytes(&std::fs::read(&args[1])?)?;
+            let mut fp = open_device(&args[2], true);
+            backup::write_backup(&mut fp, &b)?;
+            println!("{}: restored from {}", args[2], args[1]);
+        }
+        "diff" => {
+            let a = gpt::read_gpt(&mut open_device(&args[1], false), opt.sector_size)?;
+            let b = gpt::read_gpt(&mut open_device(&args[2], false), opt.sector_size)?;
+            let d = diff::diff_gpt(&a, &b, opt);
+            if !["text", "json"].contains(&opt.format.as_str()) {
+                return Err(format!("format {} unsupported", opt.format).into());
+            }
+            if opt.format == "json" {
+                let mut j = diff::diff_to_json(&d, &a, &b, opt);
+                j["a"] = args[1].clone().into();
+                j["b"] = args[2].clone().into();
+                println!("{}", serde_json::to_string_pretty(&j)?);
+            } else {
+                println!("--- {}", args[1]);
+                println!("+++ {}", args[2]);
+                if d.is_empty() {
+                    println!("no differences");
+                }
+                diff::print_diff(&d, &a, &b, opt);
+            }
+        }
+        "import" => {
+            let spec = spec::parse_spec_file(&args[1])?;
+            let mut fp = open_device(&args[2], true);
+            let mut gpt = spec::build_gpt(&spec, subr::get_size(&mut fp)?, opt)?;
+            gpt::write_pmbr(&mut fp, &gpt)?;
+            gpt::write_gpt(&mut fp, &mut gpt)?;
+            for (i, x) in gpt.ent.iter().enumerate() {
+                if !x.is_unused() {
+                    println!(
+                        "{}: entry {i} {}-{}",
+                        args[2], x.ent_lba_start, x.ent_lba_end
+                    );
+                }
+            }
+            println!(
+                "{}: GPT {} imported from {}",
+                args[2],
+                subr::uuid_to_str(&gpt.hdr.hdr_uuid),
+                args[1]
+            );
+        }
+        "types" => {
+            let pattern = args.get(1).map_or("", String::as_str);
+            if let Some(v) = subr::known_str_to_uuid(pattern) {
+                println!("{}", subr::uuid_to_str(&v));
+                return Ok(());
+            }
+            let v = subr::search_known_uuid(pattern);
+            if v.is_empty() {
+                return Err(format!("no types matching \"{pattern}\"").into());
+            }
+            if opt.format == "json" {
+                let j: Vec<_> = v
+                    .iter()
+                    .map(|x| {
+                        serde_json::json!({
+                            "guid": subr::uuid_to_str(&x.uuid),
+                            "name": x.name,
+                            "os": x.os,
+                            "description": x.desc,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&j)?);
+            } else {
+                for x in v {
+                    println!(
+                        "{} {:<36} {:<12} {}",
+                        subr::uuid_to_str(&x.uuid),
+                        x.name,
+                        x.os,
+                        x.desc
+                    );
+                }
+            }
+        }
+        "uuidgen" => {
+            let u = match args.get(1) {
+                Some(v) => {
+                    uuid::uuid_v5(&opt.namespace.unwrap_or(uuid::NAMESPACE_SEED), v.as_bytes())
+                }
+                None => uuid::uuid_random()?,
+            };
+            println!("{}", uuid::uuid_to_string(&u));
+        }
+        _ => panic!("{}", args[0]),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let progname = &args[0];
 
     let mut opts = getopts::Options::new();
     opts.optflag("", "verbose", "Enable verbose print");
     opts.optflag("", "symbol", "Print symbol name if possible");
     opts.optflag("", "noalt", "Do not dump secondary header and entries");
+    opts.optopt(
+        "",
+        "sector-size",
+        "Sector size in bytes (default 512)",
+        "<n>",
+    );
+    opts.optopt("", "entries", "Number of entries (default 128)", "<n>");
+    opts.optopt("", "disk-guid", "Disk GUID (default random)", "<guid>");
+    opts.optopt("", "part", "Entry index", "<n>");
+    opts.optopt("", "start", "First LBA of entry", "<lba>");
+    opts.optopt("", "end", "Last LBA of entry", "<lba>");
+    opts.optopt(
+        "",
+        "size",
+        "Size of entry, e.g. 512M, rounded up so that the next LBA is aligned",
+        "<size>",
+    );
+    opts.optopt(
+        "",
+        "type",
+        "Type GUID or symbol name, e.g. LINUX_DATA",
+        "<type>",
+    );
+    opts.optopt(
+        "",
+        "part-guid",
+        "Unique GUID of entry (default random)",
+        "<guid>",
+    );
+    opts.optopt("", "name", "Name of entry", "<name>");
+    opts.optopt(
+        "",
+        "attr",
+        "Attributes, e.g. 0x4 or legacy-bios-bootable",
+        "<attr>",
+    );
+    opts.optopt("", "align", "Alignment in sectors (default 2048)", "<n>");
+    opts.optflag("", "max", "Resize entry to max available");
+    opts.optflag("", "move-data", "Move data along with entry");
+    opts.optopt("", "index", "Entry indices, e.g. 1,3-5", "<list>");
+    opts.optflag("", "mapping", "Print old and new GUIDs");
+    opts.optopt(
+        "",
+        "format",
+        "Output format, text, json, sfdisk, csv, tsv or export (default text)",
+        "<fmt>",
+    );
+    opts.optflag("", "map", "Print disk layout as a bar");
+    opts.optopt("", "map-width", "Width of layout bar (default 80)", "<n>");
+    opts.optflag("", "legend", "Print legend along with layout bar");
+    opts.optopt("", "entry", "Print entry in detail", "<n>");
+    opts.optflag("", "hexdump", "Print raw header and entry bytes");
+    opts.optopt(
+        "",
+        "name-match",
+        "Only dump entries with matching name",
+        "<regex>",
+    );
+    opts.optflag(
+        "",
+        "mmap",
+        "Memory-map device instead of reading it (default dump only)",
+    );
+    opts.optopt(
+        "",
+        "seed",
+        "Derive new GUIDs from seed instead of random",
+        "<string>",
+    );
+    opts.optopt(
+        "",
+        "namespace",
+        "Namespace of uuidgen, dns, url, oid, x500 or GUID (default seed)",
+        "<ns>",
+    );
+    opts.optopt(
+        "",
+        "types-file",
+        "Custom type names (default $XDG_CONFIG_HOME/dumpgpt/types)",
+        "<path>",
+    );
     opts.optflag("v", "version", "Print version and exit");
     opts.optflag("h", "help", "Print usage and exit");
 
@@ -63,12 +476,91 @@ fn main() {
         std::process::exit(1);
     }
 
+    // before options taking type names are parsed
+    let types_file = match matches.opt_str("types-file") {
+        Some(v) => Some(std::path::PathBuf::from(v)),
+        None => subr::get_default_types_file(),
+    };
+    if let Some(f) = types_file {
+        if let Err(e) = subr::load_types_file(&f) {
+            println!("{}: {e}", f.display());
+            std::process::exit(1);
+        }
+    }
+
     let mut opt = Opt {
         ..Default::default()
     };
     opt.verbose = matches.opt_present("verbose");
     opt.symbol = matches.opt_present("symbol");
     opt.noalt = matches.opt_present("noalt");
+    opt.sector_size = get_opt_value(&matches, "sector-size", 512, subr::parse_u64);
+    if !opt.sector_size.is_power_of_two() || !(512..=65536).contains(&opt.sector_size) {
+        println!("invalid sector size {}", opt.sector_size);
+        std::process::exit(1);
+    }
+    opt.entries = get_opt_value(&matches, "entries", gpt::GPT_ENTRIES, |s| {
+        Ok(u32::try_from(subr::parse_u64(s)?)?)
+    });
+    opt.disk_guid = get_opt_value(&matches, "disk-guid", None, |s| {
+        Ok(Some(uuid::uuid_from_string(s)?))
+    });
+    opt.part = get_opt_value(&matches, "part", None, |s| {
+        Ok(Some(usize::try_from(subr::parse_u64(s)?)?))
+    });
+    opt.start = get_opt_value(&matches, "start", None, |s| Ok(Some(subr::parse_u64(s)?)));
+    opt.end = get_opt_value(&matches, "end", None, |s| Ok(Some(subr::parse_u64(s)?)));
+    opt.size = get_opt_value(&matches, "size", None, |s| Ok(Some(subr::parse_size(s)?)));
+    opt.ptype = get_opt_value(&matches, "type", None, |s| Ok(Some(subr::parse_uuid(s)?)));
+    opt.part_guid = get_opt_value(&matches, "part-guid", None, |s| {
+        Ok(Some(uuid::uuid_from_string(s)?))
+    });
+    opt.name = matches.opt_str("name");
+    opt.attr = get_opt_value(&matches, "attr", None, |s| Ok(Some(subr::parse_attr(s)?)));
+    opt.align = get_opt_value(&matches, "align", 2048, subr::parse_u64);
+    opt.max = matches.opt_present("max");
+    opt.move_data = matches.opt_present("move-data");
+    opt.index = get_opt_value(&matches, "index", None, |s| {
+        Ok(Some(subr::parse_index_list(s)?))
+    });
+    opt.mapping = matches.opt_present("mapping");
+    opt.format = matches.opt_str("format").unwrap_or("text".to_string());
+    if !["text", "json", "sfdisk", "csv", "tsv", "export"].contains(&opt.format.as_str()) {
+        println!("invalid format {}", opt.format);
+        std::process::exit(1);
+    }
+    opt.map = matches.opt_present("map");
+    opt.map_width = get_opt_value(&matches, "map-width", 80, |s| {
+        Ok(usize::try_from(subr::parse_u64(s)?)?)
+    });
+    if opt.map_width < 8 {
+        println!("invalid map width {}", opt.map_width);
+        std::process::exit(1);
+    }
+    opt.legend = matches.opt_present("legend");
+    opt.hexdump = matches.opt_present("hexdump");
+    opt.name_match = get_opt_value(&matches, "name-match", None, |s| {
+        Ok(Some(regex::Regex::new(s)?))
+    });
+    opt.entry = get_opt_value(&matches, "entry", None, |s| {
+        Ok(Some(usize::try_from(subr::parse_u64(s)?)?))
+    });
+    opt.mmap = matches.opt_present("mmap");
+    opt.seed = matches.opt_str("seed");
+    opt.namespace = get_opt_value(&matches, "namespace", None, |s| {
+        Ok(Some(match s {
+            "dns" => uuid::NAMESPACE_DNS,
+            "url" => uuid::NAMESPACE_URL,
+            "oid" => uuid::NAMESPACE_OID,
+            "x500" => uuid::NAMESPACE_X500,
+            "seed" => uuid::NAMESPACE_SEED,
+            _ => uuid::uuid_from_string(s)?,
+        }))
+    });
+    if opt.align == 0 {
+        println!("invalid alignment 0");
+        std::process::exit(1);
+    }
 
     if opt.verbose {
         print_version();
@@ -79,7 +571,120 @@ fn main() {
         std::process::exit(1);
     }
 
+    if let Some((name, args, _)) = COMMANDS.iter().find(|x| x.0 == matches.free[0]) {
+        // optional arguments are in brackets
+        let n = args
+            .split_whitespace()
+            .filter(|x| !x.starts_with('['))
+            .count();
+        if !(n..=args.split_whitespace().count()).contains(&(matches.free.len() - 1)) {
+            usage(progname, &opts);
+            std::process::exit(1);
+        }
+        if opt.mmap {
+            println!("--mmap unsupported for {name}");
+            std::process::exit(1);
+        }
+        if let Err(e) = run_command(&matches.free, &opt) {
+            println!("{name}: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // other dump modes read through gpt::read_gpt and friends
+    if opt.mmap && (opt.format != "text" || opt.map || opt.hexdump || opt.entry.is_some()) {
+        println!("--mmap only supported for the default dump");
+        std::process::exit(1);
+    }
+
     let device = &matches.free[0];
+    match opt.format.as_str() {
+        "text" if opt.map => {
+            let gpt = read_device(device, &opt);
+            match map::map_to_string(&gpt, opt.map_width, opt.legend) {
+                Ok(v) => print!("{v}"),
+                Err(e) => {
+                    println!("{device}: {e}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        "text" if opt.hexdump => {
+            let mut fp = open_device(device, false);
+            match hexdump::hexdump_gpt(&mut fp, &opt) {
+                Ok(v) => print!("{v}"),
+                Err(e) => {
+                    println!("{device}: {e}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        "text" if opt.entry.is_some() => {
+            let mut fp = open_device(device, false);
+            let f = |fp: &mut std::fs::File| -> Result<String> {
+                let gpt = gpt::read_gpt(fp, opt.sector_size)?;
+                let raw = gpt::read_table(fp, &gpt.hdr, opt.sector_size)?;
+                let alt = if opt.noalt {
+                    None
+                } else {
+                    gpt::read_header(fp, gpt.hdr.hdr_lba_alt, opt.sector_size)
+                        .ok()
+                        .filter(|x| gpt::check_header(x).is_ok())
+                };
+                detail::entry_to_string(&gpt, alt.as_ref(), &raw, opt.entry.unwrap())
+            };
+            match f(&mut fp) {
+                Ok(v) => print!("{v}"),
+                Err(e) => {
+                    println!("{device}: {e}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        "text" => (),
+        "sfdisk" | "export" => {
+            let mut gpt = read_device(device, &opt);
+            gpt::filter_entries(&mut gpt, &opt);
+            let r = if opt.format == "export" {
+                format::export_to_string(device, &gpt)
+            } else {
+                format::sfdisk_to_string(device, &gpt)
+            };
+            match r {
+                Ok(v) => print!("{v}"),
+                Err(e) => {
+                    println!("{device}: {e}");
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        "csv" | "tsv" => {
+            // one header row, so that rows of several devices can be combined
+            let sep = if opt.format == "csv" { ',' } else { '\t' };
+            println!("{}", format::TABLE_COLUMNS.join(&sep.to_string()));
+            for device in &matches.free {
+                let mut gpt = read_device(device, &opt);
+                gpt::filter_entries(&mut gpt, &opt);
+                match format::table_to_string(device, &gpt, sep) {
+                    Ok(v) => print!("{v}"),
+                    Err(e) => {
+                        println!("{device}: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            }
+            return;
+        }
+        _ => {
+            println!("format {} unsupported for dump", opt.format);
+            std::process::exit(1);
+        }
+    }
     println!("{device}");
     println!();
 
@@ -0,0 +1,256 @@
+use crate::gpt;
+use crate::subr;
+use crate::Result;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Mbr,
+    Primary,
+    Entry(usize),
+    Free,
+    Backup,
+}
+
+// first LBA, last LBA, kind
+type Region = (u64, u64, Kind);
+
+// malformed tables with valid CRCs are reported rather than drawn
+fn get_regions(gpt: &gpt::Gpt) -> Result<Vec<Region>> {
+    let hdr = &gpt.hdr;
+    let n = gpt::get_table_sectors(hdr, gpt.sector_size);
+    let (Some(primary), Some(backup)) = (
+        hdr.hdr_lba_table
+            .checked_add(n)
+            .and_then(|x| x.checked_sub(1)),
+        hdr.hdr_lba_alt.checked_sub(n),
+    ) else {
+        return Err("invalid table location".into());
+    };
+    let mut v = vec![(0, 0, Kind::Mbr), (1, primary, Kind::Primary)];
+    let mut ent = vec![];
+    for (i, x) in gpt.ent.iter().enumerate() {
+        if x.is_unused() {
+            continue;
+        }
+        if x.get_sectors().is_none() || x.ent_lba_end >= backup {
+            return Err(format!(
+                "entry {i} range {}-{} invalid",
+                x.ent_lba_start, x.ent_lba_end
+            )
+            .into());
+        }
+        ent.push((x.ent_lba_start, x.ent_lba_end, Kind::Entry(i)));
+    }
+    ent.sort_by_key(|x| x.0);
+    ent.push((backup, hdr.hdr_lba_alt, Kind::Backup));
+
+    for x in ent {
+        // overlapping entries are drawn in start order without a gap
+        let next = v.last().unwrap().1.saturating_add(1);
+        if x.0 > next {
+            v.push((next, x.0 - 1, Kind::Free));
+        }
+        v.push(x);
+    }
+    Ok(v)
+}
+
+fn get_len(x: &Region) -> u128 {
+    u128::from(x.1.saturating_sub(x.0)) + 1
+}
+
+// proportional widths, every region gets at least one column if possible
+fn get_widths(regions: &[Region], width: usize) -> Vec<usize> {
+    let total = u128::from(regions.last().unwrap().1) + 1;
+    let mut v: Vec<usize> = regions
+        .iter()
+        .map(|x| {
+            let n = get_len(x) * width as u128 / total;
+            usize::try_from(n).unwrap_or(width).max(1)
+        })
+        .collect();
+    let len = |i: usize| get_len(&regions[i]);
+    while v.iter().sum::<usize>() > width {
+        // shrink the widest region, or drop the smallest one
+        let i = match (0..v.len()).filter(|i| v[*i] > 1).max_by_key(|i| v[*i]) {
+            Some(i) => i,
+            None => (0..v.len())
+                .filter(|i| v[*i] > 0)
+                .min_by_key(|i| len(*i))
+                .unwrap(),
+        };
+        v[i] -= 1;
+    }
+    while v.iter().sum::<usize>() < width {
+        let i = (0..v.len()).max_by_key(|i| len(*i)).unwrap();
+        v[i] += 1;
+    }
+    v
+}
+
+fn get_label(gpt: &gpt::Gpt, kind: Kind) -> String {
+    match kind {
+        Kind::Mbr => "protective MBR".to_string(),
+        Kind::Primary => "primary header and entries".to_string(),
+        Kind::Entry(i) => {
+            let s = subr::known_uuid_to_str(&gpt.ent[i].ent_type);
+            if s.is_empty() {
+                i.to_string()
+            } else {
+                format!("{i}:{s}")
+            }
+        }
+        Kind::Free => "free".to_string(),
+        Kind::Backup => "backup entries and header".to_string(),
+    }
+}
+
+fn draw_region(gpt: &gpt::Gpt, kind: Kind, width: usize) -> String {
+    let c = match kind {
+        Kind::Mbr => 'M',
+        Kind::Primary => 'P',
+        Kind::Free => '.',
+        Kind::Backup => 'B',
+        Kind::Entry(_) if width < 2 => '#',
+        Kind::Entry(_) => {
+            let s: String = get_label(gpt, kind).chars().take(width - 2).collect();
+            return format!("[{s:-<0$}]", width - 2);
+        }
+    };
+    c.to_string().repeat(width)
+}
+
+pub(crate) fn map_to_string(gpt: &gpt::Gpt, width: usize, legend: bool) -> Result<String> {
+    let regions = get_regions(gpt)?;
+    let widths = get_widths(&regions, width);
+    let mut s = String::from("|");
+    for (x, w) in regions.iter().zip(&widths) {
+        s.push_str(&draw_region(gpt, x.2, *w));
+    }
+    writeln!(s, "|").unwrap();
+    let last = regions.last().unwrap().1;
+    writeln!(s, "0{last:>0$}", width + 1).unwrap();
+
+    if legend {
+        writeln!(s).unwrap();
+        for x in &regions {
+            let kind = match x.2 {
+                Kind::Entry(_) => "[]".to_string(),
+                _ => draw_region(gpt, x.2, 1),
+            };
+            writeln!(
+                s,
+                "{kind:<2} {:>12} {:>12} {:>10} {}",
+                x.0,
+                x.1,
+                subr::format_size(
+                    u64::try_from(get_len(x) * u128::from(gpt.sector_size)).unwrap_or(u64::MAX)
+                ),
+                get_label(gpt, x.2)
+            )
+            .unwrap();
+        }
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Kind;
+    use crate::edit;
+    use crate::subr;
+
+    #[test]
+    fn test_get_regions() {
+        let mut g = edit::tests::new_gpt();
+        assert_eq!(
+            super::get_regions(&g).unwrap(),
+            [
+                (0, 0, Kind::Mbr),
+                (1, 33, Kind::Primary),
+                (34, 131_038, Kind::Free),
+                (131_039, 131_071, Kind::Backup)
+            ]
+        );
+        for (i, start) in [(3, 2048), (1, 65536)] {
+            let mut opt = edit::tests::get_opt();
+            opt.part = Some(i);
+            opt.start = Some(start);
+            opt.size = Some(1 << 20);
+            edit::add_entry(&mut g, &opt).unwrap();
+        }
+        assert_eq!(
+            super::get_regions(&g).unwrap(),
+            [
+                (0, 0, Kind::Mbr),
+                (1, 33, Kind::Primary),
+                (34, 2047, Kind::Free),
+                (2048, 4095, Kind::Entry(3)),
+                (4096, 65535, Kind::Free),
+                (65536, 67583, Kind::Entry(1)),
+                (67584, 131_038, Kind::Free),
+                (131_039, 131_071, Kind::Backup)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_regions_malformed() {
+        let mut g = edit::tests::new_gpt();
+        edit::add_entry(&mut g, &edit::tests::get_opt()).unwrap();
+        let x = g.clone();
+        g.ent[0].ent_lba_end = g.ent[0].ent_lba_start - 1;
+        assert!(super::get_regions(&g).is_err());
+        g.ent[0].ent_lba_end = u64::MAX;
+        assert!(super::get_regions(&g).is_err());
+        let mut g = x.clone();
+        g.hdr.hdr_lba_alt = 1;
+        assert!(super::get_regions(&g).is_err());
+        let mut g = x;
+        g.hdr.hdr_lba_table = u64::MAX;
+        assert!(super::map_to_string(&g, 40, true).is_err());
+    }
+
+    #[test]
+    fn test_get_widths() {
+        let r = [
+            (0, 0, Kind::Mbr),
+            (1, 98, Kind::Free),
+            (99, 99, Kind::Backup),
+        ];
+        assert_eq!(super::get_widths(&r, 10), [1, 8, 1]);
+        assert_eq!(super::get_widths(&r, 3), [1, 1, 1]);
+        assert_eq!(super::get_widths(&r, 2), [0, 1, 1]);
+        let r = [(0, 49, Kind::Free), (50, 99, Kind::Entry(0))];
+        assert_eq!(super::get_widths(&r, 7).iter().sum::<usize>(), 7);
+    }
+
+    #[test]
+    fn test_map_to_string() {
+        let mut g = edit::tests::new_gpt();
+        let mut opt = edit::tests::get_opt();
+        opt.size = Some(16 << 20);
+        opt.ptype = subr::known_str_to_uuid("EFI");
+        edit::add_entry(&mut g, &opt).unwrap();
+        let mut opt = edit::tests::get_opt();
+        opt.ptype = subr::known_str_to_uuid("LINUX_SWAP");
+        opt.size = Some(16 << 20);
+        edit::add_entry(&mut g, &opt).unwrap();
+
+        let s = super::map_to_string(&g, 40, false).unwrap();
+        let v: Vec<_> = s.lines().collect();
+        assert_eq!(v.len(), 2);
+        assert_eq!(v[0], "|MP.[0:EFI---][1:LINUX_]................B|");
+        assert_eq!(v[1], format!("0{:>41}", 131_071));
+
+        let s = super::map_to_string(&g, 40, true).unwrap();
+        let v: Vec<_> = s.lines().collect();
+        assert_eq!(v.len(), 10);
+        assert_eq!(
+            v[3],
+            "M             0            0       512B protective MBR"
+        );
+        assert_eq!(v[7], "[]        34816        67583    16.0MiB 1:LINUX_SWAP");
+    }
+}
@@ -0,0 +1,540 @@
+use crate::edit;
+use crate::gpt;
+use crate::subr;
+use crate::uuid;
+use crate::Opt;
+use crate::Result;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum SpecValue {
+    Sectors(u64),
+    Bytes(u64),
+    Rest,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct PartSpec {
+    pub(crate) index: Option<usize>,
+    pub(crate) start: Option<SpecValue>,
+    pub(crate) size: Option<SpecValue>,
+    pub(crate) ptype: Option<uuid::Uuid>,
+    pub(crate) uuid: Option<uuid::Uuid>,
+    pub(crate) name: Option<String>,
+    pub(crate) attr: Option<u64>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Spec {
+    pub(crate) sector_size: Option<u64>,
+    pub(crate) entries: Option<u32>,
+    pub(crate) disk_guid: Option<uuid::Uuid>,
+    pub(crate) first_lba: Option<u64>,
+    pub(crate) last_lba: Option<u64>,
+    pub(crate) align: Option<u64>,
+    pub(crate) seed: Option<String>,
+    pub(crate) parts: Vec<PartSpec>,
+}
+
+// sfdisk type shortcuts
+const TYPE_ALIASES: [(&str, &str); 5] = [
+    ("L", "LINUX_DATA"),
+    ("S", "LINUX_SWAP"),
+    ("U", "EFI"),
+    ("R", "LINUX_RAID"),
+    ("V", "LINUX_LVM"),
+];
+
+fn parse_type(s: &str) -> Result<uuid::Uuid> {
+    match TYPE_ALIASES.iter().find(|x| x.0 == s) {
+        Some(x) => subr::parse_uuid(x.1),
+        None => subr::parse_uuid(s),
+    }
+}
+
+// plain number in sectors, number with suffix in bytes, or "+" for the rest
+fn parse_sfdisk_value(s: &str) -> Result<SpecValue> {
+    if s == "+" {
+        Ok(SpecValue::Rest)
+    } else if let Ok(v) = subr::parse_u64(s) {
+        Ok(SpecValue::Sectors(v))
+    } else {
+        Ok(SpecValue::Bytes(subr::parse_size(s)?))
+    }
+}
+
+// split by comma or whitespace outside double quotes, unquoting values
+fn split_fields(s: &str) -> Result<Vec<String>> {
+    let mut v = vec![];
+    let mut cur = String::new();
+    let mut quoted = false;
+    let mut it = s.chars();
+    while let Some(c) = it.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => match it.next() {
+                Some(c) => cur.push(c),
+                None => return Err("unterminated escape".into()),
+            },
+            ' ' | '\t' if !quoted && cur.ends_with('=') => (),
+            ',' | ' ' | '\t' if !quoted => {
+                if !cur.is_empty() {
+                    v.push(std::mem::take(&mut cur));
+                }
+            }
+            _ => cur.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".into());
+    }
+    if !cur.is_empty() {
+        v.push(cur);
+    }
+    Ok(v)
+}
+
+fn parse_sfdisk_part(line: &str) -> Result<PartSpec> {
+    let mut p = PartSpec::default();
+    let mut line = line;
+    // optional "<device> :" prefix, which determines the entry index
+    if let Some((dev, rest)) = line.split_once(':') {
+        if !dev.contains(['=', '"']) {
+            let dev = dev.trim();
+            let n = dev.len() - dev.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+            match dev[dev.len() - n..].parse::<usize>() {
+                Ok(v) if v > 0 => p.index = Some(v - 1),
+                _ => return Err(format!("invalid device \"{dev}\"").into()),
+            }
+            line = rest;
+        }
+    }
+    for x in split_fields(line)? {
+        let Some((k, v)) = x.split_once('=') else {
+            return Err(format!("invalid field \"{x}\"").into());
+        };
+        match k {
+            "start" => p.start = Some(parse_sfdisk_value(v)?),
+            "size" => p.size = Some(parse_sfdisk_value(v)?),
+            "type" => p.ptype = Some(parse_type(v)?),
+            "uuid" => p.uuid = Some(uuid::uuid_from_string(v)?),
+            "name" => p.name = Some(v.to_string()),
+            "attrs" => p.attr = Some(subr::parse_attr(v)?),
+            _ => return Err(format!("unsupported field \"{k}\"").into()),
+        }
+    }
+    Ok(p)
+}
+
+fn parse_sfdisk_line(spec: &mut Spec, line: &str) -> Result<()> {
+    if line.contains('=') {
+        spec.parts.push(parse_sfdisk_part(line)?);
+        return Ok(());
+    }
+    let Some((k, v)) = line.split_once(':') else {
+        return Err(format!("invalid line \"{line}\"").into());
+    };
+    let v = v.trim();
+    match k.trim() {
+        "label" if v == "gpt" => (),
+        "unit" if v == "sectors" => (),
+        "device" => (),
+        "label-id" => spec.disk_guid = Some(uuid::uuid_from_string(v)?),
+        "first-lba" => spec.first_lba = Some(subr::parse_u64(v)?),
+        "last-lba" => spec.last_lba = Some(subr::parse_u64(v)?),
+        "table-length" => spec.entries = Some(u32::try_from(subr::parse_u64(v)?)?),
+        "sector-size" => spec.sector_size = Some(subr::parse_u64(v)?),
+        _ => return Err(format!("unsupported header \"{line}\"").into()),
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_sfdisk(s: &str) -> Result<Spec> {
+    let mut spec = Spec::default();
+    for (n, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Err(e) = parse_sfdisk_line(&mut spec, line) {
+            return Err(format!("line {}: {e}", n + 1).into());
+        }
+    }
+    Ok(spec)
+}
+
+fn get_u64(v: &serde_json::Value) -> Result<u64> {
+    match v {
+        serde_json::Value::Number(x) => match x.as_u64() {
+            Some(x) => Ok(x),
+            None => Err(format!("invalid number {x}").into()),
+        },
+        serde_json::Value::String(x) => subr::parse_u64(x),
+        _ => Err(format!("invalid number {v}").into()),
+    }
+}
+
+fn get_str(v: &serde_json::Value) -> Result<&str> {
+    match v.as_str() {
+        Some(x) => Ok(x),
+        None => Err(format!("invalid string {v}").into()),
+    }
+}
+
+// number in bytes, string with optional suffix, or "rest"
+fn get_size(v: &serde_json::Value) -> Result<SpecValue> {
+    match v.as_str() {
+        Some("rest") => Ok(SpecValue::Rest),
+        Some(x) => Ok(SpecValue::Bytes(subr::parse_size(x)?)),
+        None => Ok(SpecValue::Bytes(get_u64(v)?)),
+    }
+}
+
+fn get_attr(v: &serde_json::Value) -> Result<u64> {
+    match v {
+        serde_json::Value::Array(a) => {
+            let mut attr = 0;
+            for x in a {
+                attr |= subr::parse_attr(get_str(x)?)?;
+            }
+            Ok(attr)
+        }
+        serde_json::Value::String(x) => subr::parse_attr(x),
+        _ => get_u64(v),
+    }
+}
+
+fn get_object(
+    v: &serde_json::Value,
+) -> Result<&serde_json::Map<std::string::String, serde_json::Value>> {
+    match v.as_object() {
+        Some(x) => Ok(x),
+        None => Err(format!("invalid object {v}").into()),
+    }
+}
+
+fn parse_part_key(p: &mut PartSpec, k: &str, v: &serde_json::Value) -> Result<()> {
+    match k {
+        "index" => p.index = Some(usize::try_from(get_u64(v)?)?),
+        "start" => p.start = Some(SpecValue::Sectors(get_u64(v)?)),
+        "size" => p.size = Some(get_size(v)?),
+        "type" => p.ptype = Some(parse_type(get_str(v)?)?),
+        "uuid" => p.uuid = Some(uuid::uuid_from_string(get_str(v)?)?),
+        "name" => p.name = Some(get_str(v)?.to_string()),
+        "attrs" => p.attr = Some(get_attr(v)?),
+        _ => return Err("unsupported key".into()),
+    }
+    Ok(())
+}
+
+// TOML and JSON specs share the same keys as sfdisk script where possible
+pub(crate) fn parse_value(v: &serde_json::Value) -> Result<Spec> {
+    let mut spec = Spec::default();
+    for (k, v) in get_object(v)? {
+        match k.as_str() {
+            "sector-size" => spec.sector_size = Some(get_u64(v)?),
+            "table-length" => spec.entries = Some(u32::try_from(get_u64(v)?)?),
+            "label-id" => spec.disk_guid = Some(uuid::uuid_from_string(get_str(v)?)?),
+            "first-lba" => spec.first_lba = Some(get_u64(v)?),
+            "last-lba" => spec.last_lba = Some(get_u64(v)?),
+            "align" => spec.align = Some(get_u64(v)?),
+            "seed" => spec.seed = Some(get_str(v)?.to_string()),
+            "partitions" => {
+                let Some(a) = v.as_array() else {
+                    return Err("partitions must be an array".into());
+                };
+                for (n, x) in a.iter().enumerate() {
+                    let mut p = PartSpec::default();
+                    for (k, v) in get_object(x)? {
+                        if let Err(e) = parse_part_key(&mut p, k, v) {
+                            return Err(format!("partition {n} \"{k}\": {e}").into());
+                        }
+                    }
+                    spec.parts.push(p);
+                }
+            }
+            _ => return Err(format!("unsupported key \"{k}\"").into()),
+        }
+    }
+    Ok(spec)
+}
+
+pub(crate) fn parse_spec_file(f: &str) -> Result<Spec> {
+    let s = std::fs::read_to_string(f)?;
+    if f.ends_with(".toml") {
+        parse_value(&toml::from_str(&s)?)
+    } else if f.ends_with(".json") {
+        parse_value(&serde_json::from_str(&s)?)
+    } else {
+        parse_sfdisk(&s)
+    }
+}
+
+fn to_sectors(v: SpecValue, sector_size: u64) -> Option<u64> {
+    match v {
+        SpecValue::Sectors(x) => Some(x),
+        SpecValue::Bytes(x) => Some(x.div_ceil(sector_size)),
+        SpecValue::Rest => None,
+    }
+}
+
+pub(crate) fn build_gpt(spec: &Spec, size: u64, opt: &Opt) -> Result<gpt::Gpt> {
+    let mut o = opt.clone();
+    if let Some(v) = spec.sector_size {
+        if !v.is_power_of_two() || !(512..=65536).contains(&v) {
+            return Err(format!("invalid sector size {v}").into());
+        }
+        o.sector_size = v;
+    }
+    if let Some(v) = spec.entries {
+        o.entries = v;
+    }
+    if spec.disk_guid.is_some() {
+        o.disk_guid = spec.disk_guid;
+    }
+    if spec.seed.is_some() {
+        o.seed.clone_from(&spec.seed);
+    }
+    if let Some(v) = spec.align {
+        if v == 0 {
+            return Err("invalid alignment 0".into());
+        }
+        o.align = v;
+    }
+    let ss = o.sector_size;
+    let mut g = edit::init_gpt(size, &o)?;
+
+    let (start, end) = (g.hdr.hdr_lba_start, g.hdr.hdr_lba_end);
+    let first = spec.first_lba.unwrap_or(start);
+    let last = spec.last_lba.unwrap_or(end);
+    if first < start || last > end || first > last {
+        return Err(format!("usable range {first}-{last} outside {start}-{end}").into());
+    }
+    g.hdr.hdr_lba_start = first;
+    g.hdr.hdr_lba_end = last;
+
+    for (n, p) in spec.parts.iter().enumerate() {
+        let mut x = o.clone();
+        x.part = p.index;
+        x.start = match p.start {
+            Some(v) => match to_sectors(v, ss) {
+                Some(v) => Some(v),
+                None => return Err(format!("partition {n}: invalid start").into()),
+            },
+            None => None,
+        };
+        x.end = None;
+        x.size = match p.size.and_then(|v| to_sectors(v, ss)) {
+            Some(v) => match v.checked_mul(ss) {
+                Some(v) => Some(v),
+                None => return Err(format!("partition {n}: size {v} sectors too large").into()),
+            },
+            None if n == spec.parts.len() - 1 => None,
+            None => return Err(format!("partition {n}: only the last one can use the rest").into()),
+        };
+        x.ptype = p.ptype;
+        x.part_guid = p.uuid;
+        x.name.clone_from(&p.name);
+        x.attr = p.attr;
+        if let Err(e) = edit::add_entry(&mut g, &x) {
+            return Err(format!("partition {n}: {e}").into());
+        }
+    }
+    Ok(g)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpecValue;
+    use crate::edit;
+    use crate::format;
+    use crate::subr;
+    use crate::uuid;
+
+    #[test]
+    fn test_split_fields() {
+        assert_eq!(
+            super::split_fields("a=1, b=\"x, \\\"y\\\"\" c=  2").unwrap(),
+            ["a=1", "b=x, \"y\"", "c=2"]
+        );
+        assert!(super::split_fields("a=\"1").is_err());
+        assert!(super::split_fields("a=\"1\\").is_err());
+    }
+
+    #[test]
+    fn test_parse_sfdisk() {
+        let s = "label: gpt
+label-id: 516E7CB4-6ECF-11D6-8FF8-00022D09712B
+device: /dev/sda
+unit: sectors
+first-lba: 34
+sector-size: 512
+
+# comment
+/dev/sda1 : start=2048, size=1MiB, type=U, name=\"EFI system\", attrs=\"RequiredPartition\"
+/dev/sda3 : size=4096, type=0FC63DAF-8483-4772-8E79-3D69D8477DE4
+size=+
+";
+        let spec = super::parse_sfdisk(s).unwrap();
+        assert_eq!(spec.sector_size, Some(512));
+        assert_eq!(spec.first_lba, Some(34));
+        assert_eq!(spec.last_lba, None);
+        assert_eq!(
+            uuid::uuid_to_string(&spec.disk_guid.unwrap()),
+            "516e7cb4-6ecf-11d6-8ff8-00022d09712b"
+        );
+        assert_eq!(spec.parts.len(), 3);
+        assert_eq!(spec.parts[0].index, Some(0));
+        assert_eq!(spec.parts[0].start, Some(SpecValue::Sectors(2048)));
+        assert_eq!(spec.parts[0].size, Some(SpecValue::Bytes(1 << 20)));
+        assert_eq!(spec.parts[0].ptype, subr::known_str_to_uuid("EFI"));
+        assert_eq!(spec.parts[0].name.as_deref(), Some("EFI system"));
+        assert_eq!(spec.parts[0].attr, Some(1));
+        assert_eq!(spec.parts[1].index, Some(2));
+        assert_eq!(spec.parts[1].size, Some(SpecValue::Sectors(4096)));
+        assert_eq!(spec.parts[1].ptype, subr::known_str_to_uuid("LINUX_DATA"));
+        assert_eq!(spec.parts[2].index, None);
+        assert_eq!(spec.parts[2].size, Some(SpecValue::Rest));
+
+        let g = super::build_gpt(&spec, 64 << 20, &edit::tests::get_opt()).unwrap();
+        assert_eq!(g.hdr.hdr_uuid, spec.disk_guid.unwrap());
+        assert_eq!(g.ent[0].ent_lba_start, 2048);
+        assert_eq!(g.ent[0].ent_lba_end, 4095);
+        assert_eq!(g.ent[2].ent_lba_start, 4096);
+        assert_eq!(g.ent[2].ent_lba_end, 8191);
+        assert_eq!(g.ent[1].ent_lba_start, 8192);
+        assert_eq!(g.ent[1].ent_lba_end, 131_038);
+        assert!(g.ent[3].is_unused());
+
+        for s in [
+            "label: dos",
+            "unit: cylinders",
+            "foo: bar",
+            "foo",
+            "start=2048, bootable",
+            "start=2048, foo=1",
+            "/dev/sda : start=2048",
+            "size=1X",
+            "type=FOO",
+            "uuid=xxx",
+        ] {
+            assert!(super::parse_sfdisk(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_parse_sfdisk_roundtrip() {
+        let mut g = edit::tests::new_gpt();
+        for (i, t) in [(0, "EFI"), (4, "LINUX_SWAP"), (1, "LINUX_DATA")] {
+            let mut opt = edit::tests::get_opt();
+            opt.part = Some(i);
+            opt.size = Some(1 << 20);
+            opt.ptype = subr::known_str_to_uuid(t);
+            opt.name = Some(format!("part {t}"));
+            opt.attr = Some(4 | 1 << 63);
+            edit::add_entry(&mut g, &opt).unwrap();
+        }
+        let s = format::sfdisk_to_string("/dev/nvme0n1", &g).unwrap();
+        let spec = super::parse_sfdisk(&s).unwrap();
+        let x = super::build_gpt(&spec, 64 << 20, &edit::tests::get_opt()).unwrap();
+        assert_eq!(x.hdr.hdr_uuid, g.hdr.hdr_uuid);
+        assert_eq!(x.ent, g.ent);
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let toml = r#"
+sector-size = 512
+table-length = 128
+align = 8
+
+[[partitions]]
+size = "512K"
+type = "EFI"
+name = "esp"
+attrs = ["required", "legacy-bios-bootable"]
+
+[[partitions]]
+index = 5
+size = 4096
+uuid = "516e7cb4-6ecf-11d6-8ff8-00022d09712b"
+
+[[partitions]]
+type = "LINUX_SWAP"
+size = "rest"
+"#;
+        let json = r#"{
+  "sector-size": 512,
+  "table-length": 128,
+  "align": 8,
+  "partitions": [
+    {"size": "512K", "type": "EFI", "name": "esp", "attrs": "required,legacy-bios-bootable"},
+    {"index": 5, "size": 4096, "uuid": "516e7cb4-6ecf-11d6-8ff8-00022d09712b"},
+    {"type": "LINUX_SWAP", "size": "rest"}
+  ]
+}"#;
+        let a = super::parse_value(&toml::from_str(toml).unwrap()).unwrap();
+        let b = super::parse_value(&serde_json::from_str(json).unwrap()).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.align, Some(8));
+        assert_eq!(a.parts[0].size, Some(SpecValue::Bytes(512 << 10)));
+        assert_eq!(a.parts[0].attr, Some(5));
+        assert_eq!(a.parts[1].size, Some(SpecValue::Bytes(4096)));
+        assert_eq!(a.parts[2].size, Some(SpecValue::Rest));
+
+        let g = super::build_gpt(&a, 64 << 20, &edit::tests::get_opt()).unwrap();
+        assert_eq!(g.ent[0].ent_lba_start, 40);
+        assert_eq!(g.ent[0].ent_lba_end, 1063);
+        assert_eq!(g.ent[5].ent_lba_start, 1064);
+        assert_eq!(g.ent[5].ent_lba_end, 1071);
+        assert_eq!(g.ent[5].ent_uuid, a.parts[1].uuid.unwrap());
+        assert_eq!(g.ent[1].ent_lba_start, 1072);
+        assert_eq!(g.ent[1].ent_lba_end, 131_038);
+
+        // seeded GUIDs are reproducible, explicit ones still take precedence
+        let v =
+            serde_json::from_str(r#"{"seed": "x", "partitions": [{"size": "1M"}, {}]}"#).unwrap();
+        let c = super::parse_value(&v).unwrap();
+        assert_eq!(c.seed.as_deref(), Some("x"));
+        let g1 = super::build_gpt(&c, 64 << 20, &edit::tests::get_opt()).unwrap();
+        let g2 = super::build_gpt(&c, 64 << 20, &edit::tests::get_opt()).unwrap();
+        assert_eq!(g1.hdr.hdr_uuid, g2.hdr.hdr_uuid);
+        assert_eq!(g1.ent, g2.ent);
+
+        for s in [
+            r#"{"foo": 1}"#,
+            r#"{"partitions": {}}"#,
+            r#"{"partitions": [{"size": -1}]}"#,
+            r#"{"partitions": [{"type": "FOO"}]}"#,
+            r#"{"partitions": [{"foo": 1}]}"#,
+            r#"{"sector-size": "x"}"#,
+            r#"{"seed": 1}"#,
+        ] {
+            let v = serde_json::from_str(s).unwrap();
+            assert!(super::parse_value(&v).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_build_gpt_invalid() {
+        let opt = edit::tests::get_opt();
+        let mut spec = super::parse_sfdisk("size=1M\nsize=1M").unwrap();
+        assert!(super::build_gpt(&spec, 64 << 20, &opt).is_ok());
+        spec.parts[0].size = Some(SpecValue::Rest);
+        assert!(super::build_gpt(&spec, 64 << 20, &opt).is_err());
+        spec.parts[0].size = Some(SpecValue::Bytes(1 << 30));
+        assert!(super::build_gpt(&spec, 64 << 20, &opt).is_err());
+
+        let spec = super::parse_sfdisk(&format!("size={}", u64::MAX)).unwrap();
+        assert_eq!(
+            super::build_gpt(&spec, 64 << 20, &opt)
+                .unwrap_err()
+                .to_string(),
+            format!("partition 0: size {} sectors too large", u64::MAX)
+        );
+
+        for s in ["sector-size: 1000", "first-lba: 10", "last-lba: 131039"] {
+            let spec = super::parse_sfdisk(s).unwrap();
+            assert!(super::build_gpt(&spec, 64 << 20, &opt).is_err(), "{s}");
+        }
+    }
+}
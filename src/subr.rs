@@ -1,750 +1,1538 @@
-use crate::gpt;
 use crate::uuid;
+use crate::Result;
+use std::io::Seek;
 
 pub(crate) fn uuid_to_str(uuid: &uuid::Uuid) -> String {
     uuid::uuid_to_string(uuid)
 }
 
-#[derive(Debug, Default)]
-struct KnownUuid {
-    uuid: uuid::Uuid,
-    name: &'static str,
+#[derive(Debug)]
+pub(crate) struct KnownUuid {
+    pub(crate) uuid: uuid::Uuid,
+    pub(crate) name: &'static str,
+    pub(crate) os: &'static str,
+    pub(crate) desc: &'static str,
 }
 
-fn get_known_uuid() -> [KnownUuid; 64] {
-    [
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x0000_0000,
-                time_mid: 0x0000,
-                time_hi_and_version: 0x0000,
-                clock_seq_hi_and_reserved: 0x00,
-                clock_seq_low: 0x00,
-                node: [0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
-            },
-            name: "UNUSED",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xc12a_7328,
-                time_mid: 0xf81f,
-                time_hi_and_version: 0x11d2,
-                clock_seq_hi_and_reserved: 0xba,
-                clock_seq_low: 0x4b,
-                node: [0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b],
-            },
-            name: "EFI",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x024d_ee41,
-                time_mid: 0x33e7,
-                time_hi_and_version: 0x11d3,
-                clock_seq_hi_and_reserved: 0x9d,
-                clock_seq_low: 0x69,
-                node: [0x00, 0x08, 0xc7, 0x81, 0xf3, 0x9f],
-            },
-            name: "MBR",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x516e_7cb4,
-                time_mid: 0x6ecf,
-                time_hi_and_version: 0x11d6,
-                clock_seq_hi_and_reserved: 0x8f,
-                clock_seq_low: 0xf8,
-                node: [0x00, 0x02, 0x2d, 0x09, 0x71, 0x2b],
-            },
-            name: "FREEBSD",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x83bd_6b9d,
-                time_mid: 0x7f41,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xbe,
-                clock_seq_low: 0x0b,
-                node: [0x00, 0x15, 0x60, 0xb8, 0x4f, 0x0f],
-            },
-            name: "FREEBSD_BOOT",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x74ba_7dd9,
-                time_mid: 0xa689,
-                time_hi_and_version: 0x11e1,
-                clock_seq_hi_and_reserved: 0xbd,
-                clock_seq_low: 0x04,
-                node: [0x00, 0xe0, 0x81, 0x28, 0x6a, 0xcf],
-            },
-            name: "FREEBSD_NANDFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x516e_7cb5,
-                time_mid: 0x6ecf,
-                time_hi_and_version: 0x11d6,
-                clock_seq_hi_and_reserved: 0x8f,
-                clock_seq_low: 0xf8,
-                node: [0x00, 0x02, 0x2d, 0x09, 0x71, 0x2b],
-            },
-            name: "FREEBSD_SWAP",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x516e_7cb6,
-                time_mid: 0x6ecf,
-                time_hi_and_version: 0x11d6,
-                clock_seq_hi_and_reserved: 0x8f,
-                clock_seq_low: 0xf8,
-                node: [0x00, 0x02, 0x2d, 0x09, 0x71, 0x2b],
-            },
-            name: "FREEBSD_UFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x516e_7cb8,
-                time_mid: 0x6ecf,
-                time_hi_and_version: 0x11d6,
-                clock_seq_hi_and_reserved: 0x8f,
-                clock_seq_low: 0xf8,
-                node: [0x00, 0x02, 0x2d, 0x09, 0x71, 0x2b],
-            },
-            name: "FREEBSD_VINUM",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x516e_7cba,
-                time_mid: 0x6ecf,
-                time_hi_and_version: 0x11d6,
-                clock_seq_hi_and_reserved: 0x8f,
-                clock_seq_low: 0xf8,
-                node: [0x00, 0x02, 0x2d, 0x09, 0x71, 0x2b],
-            },
-            name: "FREEBSD_ZFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x9e1a_2d38,
-                time_mid: 0xc612,
-                time_hi_and_version: 0x4316,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x26,
-                node: [0x8b, 0x49, 0x52, 0x1e, 0x5a, 0x8b],
-            },
-            name: "PREP_BOOT",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xebd0_a0a2,
-                time_mid: 0xb9e5,
-                time_hi_and_version: 0x4433,
-                clock_seq_hi_and_reserved: 0x87,
-                clock_seq_low: 0xc0,
-                node: [0x68, 0xb6, 0xb7, 0x26, 0x99, 0xc7],
-            },
-            name: "MS_BASIC_DATA",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xaf9b_60a0,
-                time_mid: 0x1431,
-                time_hi_and_version: 0x4f62,
-                clock_seq_hi_and_reserved: 0xbc,
-                clock_seq_low: 0x68,
-                node: [0x33, 0x11, 0x71, 0x4a, 0x69, 0xad],
-            },
-            name: "MS_LDM_DATA",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x5808_c8aa,
-                time_mid: 0x7e8f,
-                time_hi_and_version: 0x42e0,
-                clock_seq_hi_and_reserved: 0x85,
-                clock_seq_low: 0xd2,
-                node: [0xe1, 0xe9, 0x04, 0x34, 0xcf, 0xb3],
-            },
-            name: "MS_LDM_METADATA",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xde94_bba4,
-                time_mid: 0x06d1,
-                time_hi_and_version: 0x4d40,
-                clock_seq_hi_and_reserved: 0xa1,
-                clock_seq_low: 0x6a,
-                node: [0xbf, 0xd5, 0x01, 0x79, 0xd6, 0xac],
-            },
-            name: "MS_RECOVERY",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xe3c9_e316,
-                time_mid: 0x0b5c,
-                time_hi_and_version: 0x4db8,
-                clock_seq_hi_and_reserved: 0x81,
-                clock_seq_low: 0x7d,
-                node: [0xf9, 0x2d, 0xf0, 0x02, 0x15, 0xae],
-            },
-            name: "MS_RESERVED",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xe75c_af8f,
-                time_mid: 0xf680,
-                time_hi_and_version: 0x4cee,
-                clock_seq_hi_and_reserved: 0xaf,
-                clock_seq_low: 0xa3,
-                node: [0xb0, 0x01, 0xe5, 0x6e, 0xfc, 0x2d],
-            },
-            name: "MS_SPACES",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x0fc6_3daf,
-                time_mid: 0x8483,
-                time_hi_and_version: 0x4772,
-                clock_seq_hi_and_reserved: 0x8e,
-                clock_seq_low: 0x79,
-                node: [0x3d, 0x69, 0xd8, 0x47, 0x7d, 0xe4],
-            },
-            name: "LINUX_DATA",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xa19d_880f,
-                time_mid: 0x05fc,
-                time_hi_and_version: 0x4d3b,
-                clock_seq_hi_and_reserved: 0xa0,
-                clock_seq_low: 0x06,
-                node: [0x74, 0x3f, 0x0f, 0x84, 0x91, 0x1e],
-            },
-            name: "LINUX_RAID",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x0657_fd6d,
-                time_mid: 0xa4ab,
-                time_hi_and_version: 0x43c4,
-                clock_seq_hi_and_reserved: 0x84,
-                clock_seq_low: 0xe5,
-                node: [0x09, 0x33, 0xc8, 0x4b, 0x4f, 0x4f],
-            },
-            name: "LINUX_SWAP",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xe6d6_d379,
-                time_mid: 0xf507,
-                time_hi_and_version: 0x44c2,
-                clock_seq_hi_and_reserved: 0xa2,
-                clock_seq_low: 0x3c,
-                node: [0x23, 0x8f, 0x2a, 0x3d, 0xf9, 0x28],
-            },
-            name: "LINUX_LVM",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xaa31_e02a,
-                time_mid: 0x400f,
-                time_hi_and_version: 0x11db,
-                clock_seq_hi_and_reserved: 0x95,
-                clock_seq_low: 0x90,
-                node: [0x00, 0x0c, 0x29, 0x11, 0xd1, 0xb8],
-            },
-            name: "VMFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x9d27_5380,
-                time_mid: 0x40ad,
-                time_hi_and_version: 0x11db,
-                clock_seq_hi_and_reserved: 0xbf,
-                clock_seq_low: 0x97,
-                node: [0x00, 0x0c, 0x29, 0x11, 0xd1, 0xb8],
-            },
-            name: "VMKDIAG",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x9198_effc,
-                time_mid: 0x31c0,
-                time_hi_and_version: 0x11db,
-                clock_seq_hi_and_reserved: 0x8f,
-                clock_seq_low: 0x78,
-                node: [0x00, 0x0c, 0x29, 0x11, 0xd1, 0xb8],
-            },
-            name: "VMRESERVED",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x381c_fccc,
-                time_mid: 0x7288,
-                time_hi_and_version: 0x11e0,
-                clock_seq_hi_and_reserved: 0x92,
-                clock_seq_low: 0xee,
-                node: [0x00, 0x0c, 0x29, 0x11, 0xd0, 0xb2],
-            },
-            name: "VMVSANHDR",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x426F_6F74,
-                time_mid: 0x0000,
-                time_hi_and_version: 0x11aa,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x11,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_BOOT",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x4846_5300,
-                time_mid: 0x0000,
-                time_hi_and_version: 0x11aa,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x11,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_HFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x5546_5300,
-                time_mid: 0x0000,
-                time_hi_and_version: 0x11aa,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x11,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_UFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a89_8cc3,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "APPLE_ZFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x5241_4944,
-                time_mid: 0x0000,
-                time_hi_and_version: 0x11aa,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x22,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_RAID",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x5241_4944,
-                time_mid: 0x5f4f,
-                time_hi_and_version: 0x11aa,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x22,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_RAID_OFFLINE",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x4C61_6265,
-                time_mid: 0x6c00,
-                time_hi_and_version: 0x11aa,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x11,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_LABEL",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x5265_636f,
-                time_mid: 0x7665,
-                time_hi_and_version: 0x11AA,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x11,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_TV_RECOVERY",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x5374_6f72,
-                time_mid: 0x6167,
-                time_hi_and_version: 0x11AA,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x11,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_CORE_STORAGE",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x7c34_57ef,
-                time_mid: 0x0000,
-                time_hi_and_version: 0x11aa,
-                clock_seq_hi_and_reserved: 0xaa,
-                clock_seq_low: 0x11,
-                node: [0x00, 0x30, 0x65, 0x43, 0xec, 0xac],
-            },
-            name: "APPLE_APFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x49f4_8d5a,
-                time_mid: 0xb10e,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xb9,
-                clock_seq_low: 0x9b,
-                node: [0x00, 0x19, 0xd1, 0x87, 0x96, 0x48],
-            },
-            name: "NETBSD_FFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x49f4_8d82,
-                time_mid: 0xb10e,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xb9,
-                clock_seq_low: 0x9b,
-                node: [0x00, 0x19, 0xd1, 0x87, 0x96, 0x48],
-            },
-            name: "NETBSD_LFS",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x49f4_8d32,
-                time_mid: 0xb10e,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xB9,
-                clock_seq_low: 0x9B,
-                node: [0x00, 0x19, 0xd1, 0x87, 0x96, 0x48],
-            },
-            name: "NETBSD_SWAP",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x49f4_8daa,
-                time_mid: 0xb10e,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xb9,
-                clock_seq_low: 0x9b,
-                node: [0x00, 0x19, 0xd1, 0x87, 0x96, 0x48],
-            },
-            name: "NETBSD_RAID",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x2db5_19c4,
-                time_mid: 0xb10f,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xb9,
-                clock_seq_low: 0x9b,
-                node: [0x00, 0x19, 0xd1, 0x87, 0x96, 0x48],
-            },
-            name: "NETBSD_CCD",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x2db5_19ec,
-                time_mid: 0xb10f,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xb9,
-                clock_seq_low: 0x9b,
-                node: [0x00, 0x19, 0xd1, 0x87, 0x96, 0x48],
-            },
-            name: "NETBSD_CGD",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x9d08_7404,
-                time_mid: 0x1ca5,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x88,
-                clock_seq_low: 0x17,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_LABEL32",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x9d58_fdbd,
-                time_mid: 0x1ca5,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x88,
-                clock_seq_low: 0x17,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_SWAP",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x9d94_ce7c,
-                time_mid: 0x1ca5,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x88,
-                clock_seq_low: 0x17,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_UFS1",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x9dd4_478f,
-                time_mid: 0x1ca5,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x88,
-                clock_seq_low: 0x17,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_VINUM",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xdbd5_211b,
-                time_mid: 0x1ca5,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x88,
-                clock_seq_low: 0x17,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_CCD",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x3d48_ce54,
-                time_mid: 0x1d16,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x86,
-                clock_seq_low: 0x96,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_LABEL64",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xbd21_5ab2,
-                time_mid: 0x1d16,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x86,
-                clock_seq_low: 0x96,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_LEGACY",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x61dc_63ac,
-                time_mid: 0x6e38,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0x85,
-                clock_seq_low: 0x13,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_HAMMER",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x5cbb_9ad1,
-                time_mid: 0x862d,
-                time_hi_and_version: 0x11dc,
-                clock_seq_hi_and_reserved: 0xa9,
-                clock_seq_low: 0x4d,
-                node: [0x01, 0x30, 0x1b, 0xb8, 0xa9, 0xf5],
-            },
-            name: "DRAGONFLY_HAMMER2",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xcab6_e88e,
-                time_mid: 0xabf3,
-                time_hi_and_version: 0x4102,
-                clock_seq_hi_and_reserved: 0xa0,
-                clock_seq_low: 0x7a,
-                node: [0xd4, 0xbb, 0x9b, 0xe3, 0xc1, 0xd3],
-            },
-            name: "CHROMEOS_FIRMWARE",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0xfe3a_2a5d,
-                time_mid: 0x4f32,
-                time_hi_and_version: 0x41a7,
-                clock_seq_hi_and_reserved: 0xb7,
-                clock_seq_low: 0x25,
-                node: [0xac, 0xcc, 0x32, 0x85, 0xa3, 0x09],
-            },
-            name: "CHROMEOS_KERNEL",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x2e0a_753d,
-                time_mid: 0x9e48,
-                time_hi_and_version: 0x43b0,
-                clock_seq_hi_and_reserved: 0x83,
-                clock_seq_low: 0x37,
-                node: [0xb1, 0x51, 0x92, 0xcb, 0x1b, 0x5e],
-            },
-            name: "CHROMEOS_RESERVED",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x3cb8_e202,
-                time_mid: 0x3b7e,
-                time_hi_and_version: 0x47dd,
-                clock_seq_hi_and_reserved: 0x8a,
-                clock_seq_low: 0x3c,
-                node: [0x7f, 0xf2, 0xa1, 0x3c, 0xfc, 0xec],
-            },
-            name: "CHROMEOS_ROOT",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x824c_c7a0,
-                time_mid: 0x36a8,
-                time_hi_and_version: 0x11e3,
-                clock_seq_hi_and_reserved: 0x89,
-                clock_seq_low: 0x0a,
-                node: [0x95, 0x25, 0x19, 0xad, 0x3f, 0x61],
-            },
-            name: "OPENBSD_DATA",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a82_cb45,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_BOOT",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a85_cf4d,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_ROOT",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a87_c46f,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_SWAP",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a8b_642b,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_BACKUP",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a8e_f2e9,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_VAR",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a90_ba39,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_HOME",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a92_83a5,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_ALTSEC",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x6a94_5a3b,
-                time_mid: 0x1dd2,
-                time_hi_and_version: 0x11b2,
-                clock_seq_hi_and_reserved: 0x99,
-                clock_seq_low: 0xa6,
-                node: [0x08, 0x00, 0x20, 0x73, 0x66, 0x31],
-            },
-            name: "SOLARIS_RESERVED",
-        },
-        KnownUuid {
-            uuid: uuid::Uuid {
-                time_low: 0x2168_6148,
-                time_mid: 0x6449,
-                time_hi_and_version: 0x6e6f,
-                clock_seq_hi_and_reserved: 0x74,
-                clock_seq_low: 0x4e,
-                node: [0x65, 0x65, 0x64, 0x45, 0x46, 0x49],
-            },
-            name: "BIOS_BOOT",
-        },
-    ]
+const fn known(os: &'static str, name: &'static str, s: &str, desc: &'static str) -> KnownUuid {
+    KnownUuid {
+        uuid: uuid::uuid_from_str_const(s),
+        name,
+        os,
+        desc,
+    }
 }
 
-pub(crate) fn known_uuid_to_str(uuid: &uuid::Uuid) -> &'static str {
-    assert!(is_le());
+// grouped by OS family
+const KNOWN_UUID: [KnownUuid; 192] = [
+    // Generic
+    known(
+        "Generic",
+        "UNUSED",
+        "00000000-0000-0000-0000-000000000000",
+        "Unused entry",
+    ),
+    known(
+        "Generic",
+        "EFI",
+        "c12a7328-f81f-11d2-ba4b-00a0c93ec93b",
+        "EFI system partition",
+    ),
+    known(
+        "Generic",
+        "MBR",
+        "024dee41-33e7-11d3-9d69-0008c781f39f",
+        "Partition scheme inside MBR",
+    ),
+    known(
+        "Generic",
+        "BIOS_BOOT",
+        "21686148-6449-6e6f-744e-656564454649",
+        "BIOS boot partition",
+    ),
+    // Linux
+    known(
+        "Linux",
+        "LINUX_DATA",
+        "0fc63daf-8483-4772-8e79-3d69d8477de4",
+        "Linux filesystem data",
+    ),
+    known(
+        "Linux",
+        "LINUX_RAID",
+        "a19d880f-05fc-4d3b-a006-743f0f84911e",
+        "Linux RAID",
+    ),
+    known(
+        "Linux",
+        "LINUX_SWAP",
+        "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f",
+        "Linux swap",
+    ),
+    known(
+        "Linux",
+        "LINUX_LVM",
+        "e6d6d379-f507-44c2-a23c-238f2a3df928",
+        "Linux LVM",
+    ),
+    known(
+        "Linux",
+        "LINUX_RESERVED",
+        "8da63339-0007-60c0-c436-083ac8230908",
+        "Linux reserved",
+    ),
+    known(
+        "Linux",
+        "LINUX_LUKS",
+        "ca7d7ccb-63ed-4c53-861c-1742536059cc",
+        "Linux LUKS",
+    ),
+    known(
+        "Linux",
+        "LINUX_DMCRYPT",
+        "7ffec5c9-2d00-49b7-8941-3ea10a5586b7",
+        "Linux plain dm-crypt",
+    ),
+    known(
+        "Linux",
+        "LINUX_XBOOTLDR",
+        "bc13c2ff-59e6-4262-a352-b275fd6f7172",
+        "Linux extended boot loader (/boot)",
+    ),
+    known(
+        "Linux",
+        "LINUX_HOME",
+        "933ac7e1-2eb4-4f13-b844-0e14e2aef915",
+        "Linux home (/home)",
+    ),
+    known(
+        "Linux",
+        "LINUX_SRV",
+        "3b8f8425-20e0-4f3b-907f-1a25a76f98e8",
+        "Linux server data (/srv)",
+    ),
+    known(
+        "Linux",
+        "LINUX_VAR",
+        "4d21b016-b534-45c2-a9fb-5c16e091fd2d",
+        "Linux variable data (/var)",
+    ),
+    known(
+        "Linux",
+        "LINUX_VAR_TMP",
+        "7ec6f557-3bc5-4aca-b293-16ef5df639d1",
+        "Linux temporary data (/var/tmp)",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_ALPHA",
+        "6523f8ae-3eb1-4e2a-a05a-18b695ae656f",
+        "Linux root (/) for Alpha",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_ARC",
+        "d27f46ed-2919-4cb8-bd25-9531f3c16534",
+        "Linux root (/) for ARC",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_X86",
+        "44479540-f297-41b2-9af7-d131d5f0458a",
+        "Linux root (/) for x86",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_X86_64",
+        "4f68bce3-e8cd-4db1-96e7-fbcaf984b709",
+        "Linux root (/) for x86-64",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_ARM",
+        "69dad710-2ce4-4e3c-b16c-21a1d49abed3",
+        "Linux root (/) for 32-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_ARM64",
+        "b921b045-1df0-41c3-af44-4c6f280d3fae",
+        "Linux root (/) for 64-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_IA64",
+        "993d8d3d-f80e-4225-855a-9daf8ed7ea97",
+        "Linux root (/) for Itanium",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_LOONGARCH64",
+        "77055800-792c-4f94-b39a-98c91b762bb6",
+        "Linux root (/) for LoongArch 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_MIPS_LE",
+        "37c58c8a-d913-4156-a25f-48b1b64e07f0",
+        "Linux root (/) for 32-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_MIPS64_LE",
+        "700bda43-7a34-4507-b179-eeb93d7a7ca3",
+        "Linux root (/) for 64-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_PARISC",
+        "1aacdb3b-5444-4138-bd9e-e5c2239b2346",
+        "Linux root (/) for HPPA/PARISC",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_PPC",
+        "1de3f1ef-fa98-47b5-8dcd-4a860a654d78",
+        "Linux root (/) for 32-bit PowerPC",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_PPC64",
+        "912ade1d-a839-4913-8964-a10eee08fbd2",
+        "Linux root (/) for 64-bit PowerPC big-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_PPC64_LE",
+        "c31c45e6-3f39-412e-80fb-4809c4980599",
+        "Linux root (/) for 64-bit PowerPC little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_RISCV32",
+        "60d5a7fe-8e7d-435c-b714-3dd8162144e1",
+        "Linux root (/) for RISC-V 32-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_RISCV64",
+        "72ec70a6-cf74-40e6-bd49-4bda08e8f224",
+        "Linux root (/) for RISC-V 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_S390",
+        "08a7acea-624c-4a20-91e8-6e0fa67d23f9",
+        "Linux root (/) for s390",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_S390X",
+        "5eead9a9-fe09-4a1e-a1d7-520d00531306",
+        "Linux root (/) for s390x",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_TILEGX",
+        "c50cdd70-3862-4cc3-90e1-809a8c93ee2c",
+        "Linux root (/) for TILE-Gx",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_ALPHA",
+        "e18cf08c-33ec-4c0d-8246-c6c6fb3da024",
+        "Linux /usr for Alpha",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_ARC",
+        "7978a683-6316-4922-bbee-38bff5a2fecc",
+        "Linux /usr for ARC",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_X86",
+        "75250d76-8cc6-458e-bd66-bd47cc81a812",
+        "Linux /usr for x86",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_X86_64",
+        "8484680c-9521-48c6-9c11-b0720656f69e",
+        "Linux /usr for x86-64",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_ARM",
+        "7d0359a3-02b3-4f0a-865c-654403e70625",
+        "Linux /usr for 32-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_ARM64",
+        "b0e01050-ee5f-4390-949a-9101b17104e9",
+        "Linux /usr for 64-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_IA64",
+        "4301d2a6-4e3b-4b2a-bb94-9e0b2c4225ea",
+        "Linux /usr for Itanium",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_LOONGARCH64",
+        "e611c702-575c-4cbe-9a46-434fa0bf7e3f",
+        "Linux /usr for LoongArch 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_MIPS_LE",
+        "0f4868e9-9952-4706-979f-3ed3a473e947",
+        "Linux /usr for 32-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_MIPS64_LE",
+        "c97c1f32-ba06-40b4-9f22-236061b08aa8",
+        "Linux /usr for 64-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_PARISC",
+        "dc4a4480-6917-4262-a4ec-db9384949f25",
+        "Linux /usr for HPPA/PARISC",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_PPC",
+        "7d14fec5-cc71-415d-9d6c-06bf0b3c3eaf",
+        "Linux /usr for 32-bit PowerPC",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_PPC64",
+        "2c9739e2-f068-46b3-9fd0-01c5a9afbcca",
+        "Linux /usr for 64-bit PowerPC big-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_PPC64_LE",
+        "15bb03af-77e7-4d4a-b12b-c0d084f7491c",
+        "Linux /usr for 64-bit PowerPC little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_RISCV32",
+        "b933fb22-5c3f-4f91-af90-e2bb0fa50702",
+        "Linux /usr for RISC-V 32-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_RISCV64",
+        "beaec34b-8442-439b-a40b-984381ed097d",
+        "Linux /usr for RISC-V 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_S390",
+        "cd0f869b-d0fb-4ca0-b141-9ea87cc78d66",
+        "Linux /usr for s390",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_S390X",
+        "8a4f5770-50aa-4ed3-874a-99b710db6fea",
+        "Linux /usr for s390x",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_TILEGX",
+        "55497029-c7c1-44cc-aa39-815ed1558630",
+        "Linux /usr for TILE-Gx",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_ALPHA",
+        "fc56d9e9-e6e5-4c06-be32-e74407ce09a5",
+        "Linux root verity for Alpha",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_ARC",
+        "24b2d975-0f97-4521-afa1-cd531e421b8d",
+        "Linux root verity for ARC",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_X86",
+        "d13c5d3b-b5d1-422a-b29f-9454fdc89d76",
+        "Linux root verity for x86",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_X86_64",
+        "2c7357ed-ebd2-46d9-aec1-23d437ec2bf5",
+        "Linux root verity for x86-64",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_ARM",
+        "7386cdf2-203c-47a9-a498-f2ecce45a2d6",
+        "Linux root verity for 32-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_ARM64",
+        "df3300ce-d69f-4c92-978c-9bfb0f38d820",
+        "Linux root verity for 64-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_IA64",
+        "86ed10d5-b607-45bb-8957-d350f23d0571",
+        "Linux root verity for Itanium",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_LOONGARCH64",
+        "f3393b22-e9af-4613-a948-9d3bfbd0c535",
+        "Linux root verity for LoongArch 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_MIPS_LE",
+        "d7d150d2-2a04-4a33-8f12-16651205ff7b",
+        "Linux root verity for 32-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_MIPS64_LE",
+        "16b417f8-3e06-4f57-8dd2-9b5232f41aa6",
+        "Linux root verity for 64-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_PARISC",
+        "d212a430-fbc5-49f9-a983-a7feef2b8d0e",
+        "Linux root verity for HPPA/PARISC",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_PPC",
+        "98cfe649-1588-46dc-b2f0-add147424925",
+        "Linux root verity for 32-bit PowerPC",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_PPC64",
+        "9225a9a3-3c19-4d89-b4f6-eeff88f17631",
+        "Linux root verity for 64-bit PowerPC big-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_PPC64_LE",
+        "906bd944-4589-4aae-a4e4-dd983917446a",
+        "Linux root verity for 64-bit PowerPC little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_RISCV32",
+        "ae0253be-1167-4007-ac68-43926c14c5de",
+        "Linux root verity for RISC-V 32-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_RISCV64",
+        "b6ed5582-440b-4209-b8da-5ff7c419ea3d",
+        "Linux root verity for RISC-V 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_S390",
+        "7ac63b47-b25c-463b-8df8-b4a94e6c90e1",
+        "Linux root verity for s390",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_S390X",
+        "b325bfbe-c7be-4ab8-8357-139e652d2f6b",
+        "Linux root verity for s390x",
+    ),
+    known(
+        "Linux",
+        "LINUX_ROOT_VERITY_TILEGX",
+        "966061ec-28e4-4b2e-b4a5-1f0a825a1d84",
+        "Linux root verity for TILE-Gx",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_ALPHA",
+        "8cce0d25-c0d0-4a44-bd87-46331bf1df67",
+        "Linux /usr verity for Alpha",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_ARC",
+        "fca0598c-d880-4591-8c16-4eda05c7347c",
+        "Linux /usr verity for ARC",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_X86",
+        "8f461b0d-14ee-4e81-9aa9-049b6fb97abd",
+        "Linux /usr verity for x86",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_X86_64",
+        "77ff5f63-e7b6-4633-acf4-1565b864c0e6",
+        "Linux /usr verity for x86-64",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_ARM",
+        "c215d751-7bcd-4649-be90-6627490a4c05",
+        "Linux /usr verity for 32-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_ARM64",
+        "6e11a4e7-fbca-4ded-b9e9-e1a512bb664e",
+        "Linux /usr verity for 64-bit ARM",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_IA64",
+        "6a491e03-3be7-4545-8e38-83320e0ea880",
+        "Linux /usr verity for Itanium",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_LOONGARCH64",
+        "f46b2c26-59ae-48f0-9106-c50ed47f673d",
+        "Linux /usr verity for LoongArch 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_MIPS_LE",
+        "46b98d8d-b55c-4e8f-aab3-37fca7f80752",
+        "Linux /usr verity for 32-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_MIPS64_LE",
+        "3c3d61fe-b5f3-414d-bb71-8739a694a4ef",
+        "Linux /usr verity for 64-bit MIPS little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_PARISC",
+        "5843d618-ec37-48d7-9f12-cea8e08768b2",
+        "Linux /usr verity for HPPA/PARISC",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_PPC",
+        "df765d00-270e-49e5-bc75-f47bb2118b09",
+        "Linux /usr verity for 32-bit PowerPC",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_PPC64",
+        "bdb528a5-a259-475f-a87d-da53fa736a07",
+        "Linux /usr verity for 64-bit PowerPC big-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_PPC64_LE",
+        "ee2b9983-21e8-4153-86d9-b6901a54d1ce",
+        "Linux /usr verity for 64-bit PowerPC little-endian",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_RISCV32",
+        "cb1ee4e3-8cd0-4136-a0a4-aa61a32e8730",
+        "Linux /usr verity for RISC-V 32-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_RISCV64",
+        "8f1056be-9b05-47c4-81d6-be53128e5b54",
+        "Linux /usr verity for RISC-V 64-bit",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_S390",
+        "b663c618-e7bc-4d6d-90aa-11b756bb1797",
+        "Linux /usr verity for s390",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_S390X",
+        "31741cc4-1a2a-4111-a581-e00b447d2d06",
+        "Linux /usr verity for s390x",
+    ),
+    known(
+        "Linux",
+        "LINUX_USR_VERITY_TILEGX",
+        "2fb4bf56-07fa-42da-8132-6b139f2026ae",
+        "Linux /usr verity for TILE-Gx",
+    ),
+    // ChromeOS
+    known(
+        "ChromeOS",
+        "CHROMEOS_FIRMWARE",
+        "cab6e88e-abf3-4102-a07a-d4bb9be3c1d3",
+        "ChromeOS firmware",
+    ),
+    known(
+        "ChromeOS",
+        "CHROMEOS_KERNEL",
+        "fe3a2a5d-4f32-41a7-b725-accc3285a309",
+        "ChromeOS kernel",
+    ),
+    known(
+        "ChromeOS",
+        "CHROMEOS_RESERVED",
+        "2e0a753d-9e48-43b0-8337-b15192cb1b5e",
+        "ChromeOS reserved",
+    ),
+    known(
+        "ChromeOS",
+        "CHROMEOS_ROOT",
+        "3cb8e202-3b7e-47dd-8a3c-7ff2a13cfcec",
+        "ChromeOS root filesystem",
+    ),
+    // Android
+    known(
+        "Android",
+        "ANDROID_BOOTLOADER",
+        "2568845d-2332-4675-bc39-8fa5a4748d15",
+        "Android bootloader",
+    ),
+    known(
+        "Android",
+        "ANDROID_BOOTLOADER2",
+        "114eaffe-1552-4022-b26e-9b053604cf84",
+        "Android bootloader 2",
+    ),
+    known(
+        "Android",
+        "ANDROID_BOOT",
+        "49a4d17f-93a3-45c1-a0de-f50b2ebe2599",
+        "Android boot",
+    ),
+    known(
+        "Android",
+        "ANDROID_RECOVERY",
+        "4177c722-9e92-4aab-8644-43502bfd5506",
+        "Android recovery",
+    ),
+    known(
+        "Android",
+        "ANDROID_MISC",
+        "ef32a33b-a409-486c-9141-9ffb711f6266",
+        "Android misc",
+    ),
+    known(
+        "Android",
+        "ANDROID_METADATA",
+        "20ac26be-20b7-11e3-84c5-6cfdb94711e9",
+        "Android metadata",
+    ),
+    known(
+        "Android",
+        "ANDROID_SYSTEM",
+        "38f428e6-d326-425d-9140-6e0ea133647c",
+        "Android system",
+    ),
+    known(
+        "Android",
+        "ANDROID_CACHE",
+        "a893ef21-e428-470a-9e55-0668fd91a2d9",
+        "Android cache",
+    ),
+    known(
+        "Android",
+        "ANDROID_DATA",
+        "dc76dda9-5ac1-491c-af42-a82591580c0d",
+        "Android data",
+    ),
+    known(
+        "Android",
+        "ANDROID_PERSISTENT",
+        "ebc597d0-2053-4b15-8b64-e0aac75f4db1",
+        "Android persistent",
+    ),
+    known(
+        "Android",
+        "ANDROID_VENDOR",
+        "c5a0aeec-13ea-11e5-a1b1-001e67ca0c3c",
+        "Android vendor",
+    ),
+    known(
+        "Android",
+        "ANDROID_CONFIG",
+        "bd59408b-4514-490d-bf12-9878d963f378",
+        "Android config",
+    ),
+    known(
+        "Android",
+        "ANDROID_FACTORY",
+        "8f68cc74-c5e5-48da-be91-a0c8c15e9c80",
+        "Android factory",
+    ),
+    known(
+        "Android",
+        "ANDROID_FACTORY_ALT",
+        "9fdaa6ef-4b3f-40d2-ba8d-bff16bfb887b",
+        "Android factory (alternate)",
+    ),
+    known(
+        "Android",
+        "ANDROID_FASTBOOT",
+        "767941d0-2085-11e3-ad3b-6cfdb94711e9",
+        "Android fastboot/tertiary",
+    ),
+    known(
+        "Android",
+        "ANDROID_OEM",
+        "ac6d7924-eb71-4df8-b48d-e267b27148ff",
+        "Android OEM",
+    ),
+    // Fuchsia
+    known(
+        "Fuchsia",
+        "FUCHSIA_BOOTLOADER",
+        "fe8a2634-5e2e-46ba-99e3-3a192091a350",
+        "Fuchsia bootloader",
+    ),
+    known(
+        "Fuchsia",
+        "FUCHSIA_ZIRCON",
+        "9b37fff6-2e58-466a-983a-f7926d0b04e0",
+        "Fuchsia Zircon boot image",
+    ),
+    known(
+        "Fuchsia",
+        "FUCHSIA_VBMETA",
+        "421a8bfc-85d9-4d85-acda-b64eec0133e9",
+        "Fuchsia verified boot metadata",
+    ),
+    known(
+        "Fuchsia",
+        "FUCHSIA_FVM",
+        "49fd7cb8-df15-4e73-b9d9-992070127f0f",
+        "Fuchsia volume manager",
+    ),
+    known(
+        "Fuchsia",
+        "FUCHSIA_SYS_CONFIG",
+        "d9fd4535-106c-4cec-8d37-dfc020ca87cb",
+        "Fuchsia durable system data",
+    ),
+    known(
+        "Fuchsia",
+        "FUCHSIA_BOOT_CONFIG",
+        "a409e16b-78aa-4acc-995c-302352621a41",
+        "Fuchsia durable bootloader data",
+    ),
+    known(
+        "Fuchsia",
+        "FUCHSIA_FACTORY_SYS",
+        "f95d940e-caba-4578-9b93-bb6c90f29d3e",
+        "Fuchsia factory system data",
+    ),
+    known(
+        "Fuchsia",
+        "FUCHSIA_FACTORY_BOOT",
+        "10b8dbaa-d2bf-42a9-98c6-a7c5db3701e7",
+        "Fuchsia factory bootloader data",
+    ),
+    // Windows
+    known(
+        "Windows",
+        "MS_BASIC_DATA",
+        "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
+        "Microsoft basic data",
+    ),
+    known(
+        "Windows",
+        "MS_LDM_DATA",
+        "af9b60a0-1431-4f62-bc68-3311714a69ad",
+        "Windows LDM data",
+    ),
+    known(
+        "Windows",
+        "MS_LDM_METADATA",
+        "5808c8aa-7e8f-42e0-85d2-e1e90434cfb3",
+        "Windows LDM metadata",
+    ),
+    known(
+        "Windows",
+        "MS_RECOVERY",
+        "de94bba4-06d1-4d40-a16a-bfd50179d6ac",
+        "Windows recovery environment",
+    ),
+    known(
+        "Windows",
+        "MS_RESERVED",
+        "e3c9e316-0b5c-4db8-817d-f92df00215ae",
+        "Microsoft reserved",
+    ),
+    known(
+        "Windows",
+        "MS_SPACES",
+        "e75caf8f-f680-4cee-afa3-b001e56efc2d",
+        "Windows storage spaces",
+    ),
+    known(
+        "Windows",
+        "MS_STORAGE_REPLICA",
+        "558d43c5-a1ac-43c0-aac8-d1472b2923d1",
+        "Windows storage replica",
+    ),
+    // Apple
+    known(
+        "Apple",
+        "APPLE_BOOT",
+        "426f6f74-0000-11aa-aa11-00306543ecac",
+        "Apple boot",
+    ),
+    known(
+        "Apple",
+        "APPLE_HFS",
+        "48465300-0000-11aa-aa11-00306543ecac",
+        "Apple HFS+",
+    ),
+    known(
+        "Apple",
+        "APPLE_UFS",
+        "55465300-0000-11aa-aa11-00306543ecac",
+        "Apple UFS",
+    ),
+    known(
+        "Apple",
+        "APPLE_ZFS",
+        "6a898cc3-1dd2-11b2-99a6-080020736631",
+        "Apple ZFS",
+    ),
+    known(
+        "Apple",
+        "APPLE_RAID",
+        "52414944-0000-11aa-aa22-00306543ecac",
+        "Apple RAID",
+    ),
+    known(
+        "Apple",
+        "APPLE_RAID_OFFLINE",
+        "52414944-5f4f-11aa-aa22-00306543ecac",
+        "Apple RAID offline",
+    ),
+    known(
+        "Apple",
+        "APPLE_LABEL",
+        "4c616265-6c00-11aa-aa11-00306543ecac",
+        "Apple label",
+    ),
+    known(
+        "Apple",
+        "APPLE_TV_RECOVERY",
+        "5265636f-7665-11aa-aa11-00306543ecac",
+        "Apple TV recovery",
+    ),
+    known(
+        "Apple",
+        "APPLE_CORE_STORAGE",
+        "53746f72-6167-11aa-aa11-00306543ecac",
+        "Apple Core Storage",
+    ),
+    known(
+        "Apple",
+        "APPLE_APFS",
+        "7c3457ef-0000-11aa-aa11-00306543ecac",
+        "Apple APFS",
+    ),
+    // FreeBSD
+    known(
+        "FreeBSD",
+        "FREEBSD",
+        "516e7cb4-6ecf-11d6-8ff8-00022d09712b",
+        "FreeBSD disklabel",
+    ),
+    known(
+        "FreeBSD",
+        "FREEBSD_BOOT",
+        "83bd6b9d-7f41-11dc-be0b-001560b84f0f",
+        "FreeBSD boot",
+    ),
+    known(
+        "FreeBSD",
+        "FREEBSD_NANDFS",
+        "74ba7dd9-a689-11e1-bd04-00e081286acf",
+        "FreeBSD NANDFS",
+    ),
+    known(
+        "FreeBSD",
+        "FREEBSD_SWAP",
+        "516e7cb5-6ecf-11d6-8ff8-00022d09712b",
+        "FreeBSD swap",
+    ),
+    known(
+        "FreeBSD",
+        "FREEBSD_UFS",
+        "516e7cb6-6ecf-11d6-8ff8-00022d09712b",
+        "FreeBSD UFS",
+    ),
+    known(
+        "FreeBSD",
+        "FREEBSD_VINUM",
+        "516e7cb8-6ecf-11d6-8ff8-00022d09712b",
+        "FreeBSD Vinum",
+    ),
+    known(
+        "FreeBSD",
+        "FREEBSD_ZFS",
+        "516e7cba-6ecf-11d6-8ff8-00022d09712b",
+        "FreeBSD ZFS",
+    ),
+    // MidnightBSD
+    known(
+        "MidnightBSD",
+        "MIDNIGHTBSD_DATA",
+        "85d5e45a-237c-11e1-b4b3-e89a8f7fc3a7",
+        "MidnightBSD data",
+    ),
+    known(
+        "MidnightBSD",
+        "MIDNIGHTBSD_BOOT",
+        "85d5e45e-237c-11e1-b4b3-e89a8f7fc3a7",
+        "MidnightBSD boot",
+    ),
+    known(
+        "MidnightBSD",
+        "MIDNIGHTBSD_SWAP",
+        "85d5e45b-237c-11e1-b4b3-e89a8f7fc3a7",
+        "MidnightBSD swap",
+    ),
+    known(
+        "MidnightBSD",
+        "MIDNIGHTBSD_UFS",
+        "0394ef8b-237e-11e1-b4b3-e89a8f7fc3a7",
+        "MidnightBSD UFS",
+    ),
+    known(
+        "MidnightBSD",
+        "MIDNIGHTBSD_VINUM",
+        "85d5e45c-237c-11e1-b4b3-e89a8f7fc3a7",
+        "MidnightBSD Vinum",
+    ),
+    known(
+        "MidnightBSD",
+        "MIDNIGHTBSD_ZFS",
+        "85d5e45d-237c-11e1-b4b3-e89a8f7fc3a7",
+        "MidnightBSD ZFS",
+    ),
+    // NetBSD
+    known(
+        "NetBSD",
+        "NETBSD_FFS",
+        "49f48d5a-b10e-11dc-b99b-0019d1879648",
+        "NetBSD FFS",
+    ),
+    known(
+        "NetBSD",
+        "NETBSD_LFS",
+        "49f48d82-b10e-11dc-b99b-0019d1879648",
+        "NetBSD LFS",
+    ),
+    known(
+        "NetBSD",
+        "NETBSD_SWAP",
+        "49f48d32-b10e-11dc-b99b-0019d1879648",
+        "NetBSD swap",
+    ),
+    known(
+        "NetBSD",
+        "NETBSD_RAID",
+        "49f48daa-b10e-11dc-b99b-0019d1879648",
+        "NetBSD RAID",
+    ),
+    known(
+        "NetBSD",
+        "NETBSD_CCD",
+        "2db519c4-b10f-11dc-b99b-0019d1879648",
+        "NetBSD concatenated",
+    ),
+    known(
+        "NetBSD",
+        "NETBSD_CGD",
+        "2db519ec-b10f-11dc-b99b-0019d1879648",
+        "NetBSD encrypted",
+    ),
+    // OpenBSD
+    known(
+        "OpenBSD",
+        "OPENBSD_DATA",
+        "824cc7a0-36a8-11e3-890a-952519ad3f61",
+        "OpenBSD data",
+    ),
+    // DragonFly
+    known(
+        "DragonFly",
+        "DRAGONFLY_LABEL32",
+        "9d087404-1ca5-11dc-8817-01301bb8a9f5",
+        "DragonFly disklabel32",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_SWAP",
+        "9d58fdbd-1ca5-11dc-8817-01301bb8a9f5",
+        "DragonFly swap",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_UFS1",
+        "9d94ce7c-1ca5-11dc-8817-01301bb8a9f5",
+        "DragonFly UFS1",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_VINUM",
+        "9dd4478f-1ca5-11dc-8817-01301bb8a9f5",
+        "DragonFly Vinum",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_CCD",
+        "dbd5211b-1ca5-11dc-8817-01301bb8a9f5",
+        "DragonFly CCD",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_LABEL64",
+        "3d48ce54-1d16-11dc-8696-01301bb8a9f5",
+        "DragonFly disklabel64",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_LEGACY",
+        "bd215ab2-1d16-11dc-8696-01301bb8a9f5",
+        "DragonFly legacy",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_HAMMER",
+        "61dc63ac-6e38-11dc-8513-01301bb8a9f5",
+        "DragonFly HAMMER",
+    ),
+    known(
+        "DragonFly",
+        "DRAGONFLY_HAMMER2",
+        "5cbb9ad1-862d-11dc-a94d-01301bb8a9f5",
+        "DragonFly HAMMER2",
+    ),
+    // Solaris
+    known(
+        "Solaris",
+        "SOLARIS_BOOT",
+        "6a82cb45-1dd2-11b2-99a6-080020736631",
+        "Solaris boot",
+    ),
+    known(
+        "Solaris",
+        "SOLARIS_ROOT",
+        "6a85cf4d-1dd2-11b2-99a6-080020736631",
+        "Solaris root",
+    ),
+    known(
+        "Solaris",
+        "SOLARIS_SWAP",
+        "6a87c46f-1dd2-11b2-99a6-080020736631",
+        "Solaris swap",
+    ),
+    known(
+        "Solaris",
+        "SOLARIS_BACKUP",
+        "6a8b642b-1dd2-11b2-99a6-080020736631",
+        "Solaris backup",
+    ),
+    known(
+        "Solaris",
+        "SOLARIS_VAR",
+        "6a8ef2e9-1dd2-11b2-99a6-080020736631",
+        "Solaris /var",
+    ),
+    known(
+        "Solaris",
+        "SOLARIS_HOME",
+        "6a90ba39-1dd2-11b2-99a6-080020736631",
+        "Solaris /home",
+    ),
+    known(
+        "Solaris",
+        "SOLARIS_ALTSEC",
+        "6a9283a5-1dd2-11b2-99a6-080020736631",
+        "Solaris alternate sector",
+    ),
+    known(
+        "Solaris",
+        "SOLARIS_RESERVED",
+        "6a945a3b-1dd2-11b2-99a6-080020736631",
+        "Solaris reserved",
+    ),
+    // VMware
+    known(
+        "VMware",
+        "VMFS",
+        "aa31e02a-400f-11db-9590-000c2911d1b8",
+        "VMware VMFS",
+    ),
+    known(
+        "VMware",
+        "VMKDIAG",
+        "9d275380-40ad-11db-bf97-000c2911d1b8",
+        "VMware diagnostic",
+    ),
+    known(
+        "VMware",
+        "VMRESERVED",
+        "9198effc-31c0-11db-8f78-000c2911d1b8",
+        "VMware reserved",
+    ),
+    known(
+        "VMware",
+        "VMVSANHDR",
+        "381cfccc-7288-11e0-92ee-000c2911d0b2",
+        "VMware vSAN",
+    ),
+    // IBM
+    known(
+        "IBM",
+        "PREP_BOOT",
+        "9e1a2d38-c612-4316-aa26-8b49521e5a8b",
+        "PowerPC PReP boot",
+    ),
+    known(
+        "IBM",
+        "IBM_GPFS",
+        "37affc90-ef7d-4e96-91c3-2d7ae055b174",
+        "IBM GPFS",
+    ),
+    // Haiku
+    known(
+        "Haiku",
+        "HAIKU_BFS",
+        "42465331-3ba3-10f1-802a-4861696b7521",
+        "Haiku BFS",
+    ),
+    // Ceph
+    known(
+        "Ceph",
+        "CEPH_OSD",
+        "4fbd7e29-9d25-41b8-afd0-062c0ceff05d",
+        "Ceph OSD",
+    ),
+    known(
+        "Ceph",
+        "CEPH_DMCRYPT_OSD",
+        "4fbd7e29-9d25-41b8-afd0-5ec00ceff05d",
+        "Ceph dm-crypt OSD",
+    ),
+    known(
+        "Ceph",
+        "CEPH_JOURNAL",
+        "45b0969e-9b03-4f30-b4c6-b4b80ceff106",
+        "Ceph journal",
+    ),
+    known(
+        "Ceph",
+        "CEPH_DMCRYPT_JOURNAL",
+        "45b0969e-9b03-4f30-b4c6-5ec00ceff106",
+        "Ceph dm-crypt journal",
+    ),
+    known(
+        "Ceph",
+        "CEPH_DISK_IN_CREATION",
+        "89c57f98-2fe5-4dc0-89c1-f3ad0ceff2be",
+        "Ceph disk in creation",
+    ),
+    known(
+        "Ceph",
+        "CEPH_DMCRYPT_DISK_IN_CREATION",
+        "89c57f98-2fe5-4dc0-89c1-5ec00ceff2be",
+        "Ceph dm-crypt disk in creation",
+    ),
+    known(
+        "Ceph",
+        "CEPH_BLOCK",
+        "cafecafe-9b03-4f30-b4c6-b4b80ceff106",
+        "Ceph block",
+    ),
+    known(
+        "Ceph",
+        "CEPH_BLOCK_DB",
+        "30cd0809-c2b2-499c-8879-2d6b78529876",
+        "Ceph block DB",
+    ),
+    known(
+        "Ceph",
+        "CEPH_BLOCK_WAL",
+        "5ce17fce-4087-4169-b7ff-056cc58473f9",
+        "Ceph block write-ahead log",
+    ),
+    known(
+        "Ceph",
+        "CEPH_LOCKBOX",
+        "fb3aabf9-d25f-47cc-bf5e-721d1816496b",
+        "Ceph lockbox for dm-crypt keys",
+    ),
+    // QNX
+    known(
+        "QNX",
+        "QNX6",
+        "cef5a9ad-73bc-4601-89f3-cdeeeee321a1",
+        "QNX6 power-safe filesystem",
+    ),
+];
+
+// user types take precedence over built-in ones, loaded once at startup,
+// built-in names stay valid unless redefined
+static USER_UUID: std::sync::OnceLock<Vec<KnownUuid>> = std::sync::OnceLock::new();
+
+// merged type list and lookup tables keyed by GUID and upper case name,
+// built once on first lookup after user types are loaded
+struct KnownIndex {
+    list: Vec<&'static KnownUuid>,
+    by_uuid: std::collections::HashMap<uuid::Uuid, &'static KnownUuid>,
+    by_name: std::collections::HashMap<String, &'static KnownUuid>,
+}
+
+static KNOWN_INDEX: std::sync::OnceLock<KnownIndex> = std::sync::OnceLock::new();
+
+fn get_known_index() -> &'static KnownIndex {
+    KNOWN_INDEX.get_or_init(|| {
+        let user = USER_UUID.get().map_or(&[][..], Vec::as_slice);
+        let list: Vec<_> = user
+            .iter()
+            .chain(
+                KNOWN_UUID
+                    .iter()
+                    .filter(|x| !user.iter().any(|y| y.name.eq_ignore_ascii_case(x.name))),
+            )
+            .collect();
+        let mut by_uuid = std::collections::HashMap::with_capacity(list.len());
+        let mut by_name = std::collections::HashMap::with_capacity(list.len());
+        for x in &list {
+            // first one wins, user types come first
+            by_uuid.entry(x.uuid).or_insert(*x);
+            by_name.entry(x.name.to_ascii_uppercase()).or_insert(*x);
+        }
+        KnownIndex {
+            list,
+            by_uuid,
+            by_name,
+        }
+    })
+}
+
+pub(crate) fn get_known_uuid() -> &'static [&'static KnownUuid] {
+    &get_known_index().list
+}
+
+fn user_type(guid: &str, name: &str, desc: &str, os: &str) -> Result<KnownUuid> {
+    if name.is_empty() || name.len() > 36 || name.contains(char::is_whitespace) {
+        return Err(format!("invalid type name \"{name}\"").into());
+    }
+    Ok(KnownUuid {
+        uuid: uuid::uuid_from_string(guid)?,
+        name: name.to_string().leak(),
+        os: os.to_string().leak(),
+        desc: desc.to_string().leak(),
+    })
+}
+
+// text lines "<guid> <name> [<description>]", or TOML array of tables
+// [[types]] with guid, name, and optional description and os keys
+pub(crate) fn parse_types(s: &str, toml: bool) -> Result<Vec<KnownUuid>> {
+    let mut v = vec![];
+    if toml {
+        let t: serde_json::Value = toml::from_str(s)?;
+        let Some(a) = t.get("types").and_then(|x| x.as_array()) else {
+            return Err("types must be an array of tables".into());
+        };
+        for (i, x) in a.iter().enumerate() {
+            let get = |k| x.get(k).and_then(|x| x.as_str());
+            let (Some(guid), Some(name)) = (get("guid"), get("name")) else {
+                return Err(format!("type {i}: guid and name required").into());
+            };
+            let desc = get("description").unwrap_or(name);
+            v.push(user_type(guid, name, desc, get("os").unwrap_or("User"))?);
+        }
+        return Ok(v);
+    }
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut it = line.splitn(3, char::is_whitespace);
+        let (Some(guid), Some(name)) = (it.next(), it.next()) else {
+            return Err(format!("line {}: invalid line \"{line}\"", i + 1).into());
+        };
+        let desc = it.next().map_or(name, str::trim);
+        match user_type(guid, name, desc, "User") {
+            Ok(x) => v.push(x),
+            Err(e) => return Err(format!("line {}: {e}", i + 1).into()),
+        }
+    }
+    Ok(v)
+}
+
+// $XDG_CONFIG_HOME/dumpgpt/types[.toml], or under ~/.config
+pub(crate) fn get_default_types_file() -> Option<std::path::PathBuf> {
+    let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(v) if !v.is_empty() => std::path::PathBuf::from(v),
+        _ => std::path::Path::new(&std::env::var_os("HOME")?).join(".config"),
+    };
+    ["types.toml", "types"]
+        .iter()
+        .map(|x| dir.join("dumpgpt").join(x))
+        .find(|x| x.is_file())
+}
+
+pub(crate) fn load_types_file(f: &std::path::Path) -> Result<()> {
+    let v = parse_types(
+        &std::fs::read_to_string(f)?,
+        f.extension().is_some_and(|x| x == "toml"),
+    )?;
+    if KNOWN_INDEX.get().is_some() || USER_UUID.set(v).is_err() {
+        return Err("types already loaded".into());
+    }
+    Ok(())
+}
 
-    for x in &get_known_uuid() {
-        if *uuid == x.uuid {
+pub(crate) fn find_known_uuid(uuid: &uuid::Uuid) -> Option<&'static KnownUuid> {
+    get_known_index().by_uuid.get(uuid).copied()
+}
+
+// case-insensitive substring match of GUID, name, OS family or description
+pub(crate) fn search_known_uuid(pattern: &str) -> Vec<&'static KnownUuid> {
+    // a full GUID in any accepted form matches its canonical string
+    let p = match uuid::uuid_from_string(pattern) {
+        Ok(x) => uuid_to_str(&x),
+        Err(_) => pattern.to_lowercase(),
+    };
+    get_known_uuid()
+        .iter()
+        .filter(|x| {
+            [uuid_to_str(&x.uuid).as_str(), x.name, x.os, x.desc]
+                .iter()
+                .any(|s| s.to_lowercase().contains(&p))
+        })
+        .copied()
+        .collect()
+}
+
+pub(crate) fn known_uuid_to_str(uuid: &uuid::Uuid) -> &'static str {
+    match find_known_uuid(uuid) {
+        Some(x) => {
             assert!(x.name.len() <= 36);
-            return x.name;
+            x.name
         }
+        None => "",
     }
-    ""
 }
 
-pub(crate) fn is_le() -> bool {
-    cfg!(target_endian = "little")
+pub(crate) fn crc32(buf: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for x in buf {
+        crc ^= u32::from(*x);
+        for _ in 0..8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ 0xedb8_8320
+            };
+        }
+    }
+    !crc
 }
 
-pub(crate) fn assert_ds() {
-    assert_eq!(std::mem::size_of::<gpt::GptHdr>(), 92 + 4);
-    assert_eq!(std::mem::size_of::<gpt::GptEnt>(), 128);
-    assert_eq!(std::mem::size_of::<uuid::Uuid>(), 16);
+pub(crate) fn parse_u64(s: &str) -> Result<u64> {
+    let r = if let Some(x) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(x, 16)
+    } else {
+        s.parse()
+    };
+    match r {
+        Ok(v) => Ok(v),
+        Err(e) => Err(format!("invalid number \"{s}\": {e}").into()),
+    }
+}
+
+pub(crate) fn get_size(fp: &mut std::fs::File) -> Result<u64> {
+    // works for both regular files and block devices
+    let size = fp.seek(std::io::SeekFrom::End(0))?;
+    fp.rewind()?;
+    Ok(size)
+}
+
+pub(crate) fn known_str_to_uuid(s: &str) -> Option<uuid::Uuid> {
+    get_known_index()
+        .by_name
+        .get(&s.to_ascii_uppercase())
+        .map(|x| x.uuid)
+}
+
+// accept either a known symbol name or a GUID string
+pub(crate) fn parse_uuid(s: &str) -> Result<uuid::Uuid> {
+    match known_str_to_uuid(s) {
+        Some(v) => Ok(v),
+        None => uuid::uuid_from_string(s),
+    }
+}
+
+pub(crate) fn parse_size(s: &str) -> Result<u64> {
+    let t = s.trim_end_matches("iB").trim_end_matches('B');
+    let (t, shift) = match t.chars().last() {
+        Some('K' | 'k') => (&t[..t.len() - 1], 10),
+        Some('M' | 'm') => (&t[..t.len() - 1], 20),
+        Some('G' | 'g') => (&t[..t.len() - 1], 30),
+        Some('T' | 't') => (&t[..t.len() - 1], 40),
+        Some('P' | 'p') => (&t[..t.len() - 1], 50),
+        _ => (t, 0),
+    };
+    match parse_u64(t)?.checked_mul(1 << shift) {
+        Some(v) => Ok(v),
+        None => Err(format!("size \"{s}\" too large").into()),
+    }
+}
+
+// e.g. 1048576 -> "1.0MiB"
+pub(crate) fn format_size(size: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    let mut i = 0;
+    while i < units.len() - 1 && size >= 1 << (10 * (i + 1)) {
+        i += 1;
+    }
+    if i == 0 {
+        format!("{size}B")
+    } else {
+        let v = size as f64 / (1u64 << (10 * i)) as f64;
+        format!("{v:.1}{}", units[i])
+    }
+}
+
+// 16 bytes per line with offset and ASCII columns, like hexdump -C
+pub(crate) fn hexdump(buf: &[u8], offset: u64) -> Vec<String> {
+    let mut v = vec![];
+    for (i, x) in buf.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (j, b) in x.iter().enumerate() {
+            hex.push_str(if j == 8 { "  " } else { " " });
+            hex.push_str(&format!("{b:02x}"));
+        }
+        let ascii: String = x
+            .iter()
+            .map(|b| {
+                if b.is_ascii_graphic() || *b == b' ' {
+                    char::from(*b)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        v.push(format!(
+            "{:08x} {hex:<49}  |{ascii}|",
+            offset + 16 * u64::try_from(i).unwrap()
+        ));
+    }
+    v
+}
+
+// e.g. "1,3-5" -> [1, 3, 4, 5]
+pub(crate) fn parse_index_list(s: &str) -> Result<Vec<usize>> {
+    let mut v = vec![];
+    for x in s.split(',').map(str::trim) {
+        let (a, b) = match x.split_once('-') {
+            Some((a, b)) => (parse_u64(a)?, parse_u64(b)?),
+            None => (parse_u64(x)?, parse_u64(x)?),
+        };
+        if a > b {
+            return Err(format!("invalid range \"{x}\"").into());
+        }
+        for i in a..=b {
+            let i = usize::try_from(i)?;
+            if !v.contains(&i) {
+                v.push(i);
+            }
+        }
+    }
+    Ok(v)
+}
+
+// bit, name, sfdisk name
+const ATTR_NAMES: [(u32, &str, &str); 3] = [
+    (0, "required", "RequiredPartition"),
+    (1, "no-block-io", "NoBlockIOProtocol"),
+    (2, "legacy-bios-bootable", "LegacyBIOSBootable"),
+];
+
+pub(crate) fn attr_to_names(attr: u64, sfdisk: bool) -> Vec<String> {
+    let mut v = vec![];
+    for (bit, name, sname) in &ATTR_NAMES {
+        if attr & (1 << bit) != 0 {
+            v.push(if sfdisk { sname } else { name }.to_string());
+        }
+    }
+    for bit in 48..64 {
+        if attr & (1 << bit) != 0 {
+            v.push(format!("{}:{bit}", if sfdisk { "GUID" } else { "guid" }));
+        }
+    }
+    // sfdisk has no syntax for reserved bits
+    let rest = attr & 0x0000_ffff_ffff_fff8;
+    if !sfdisk && rest != 0 {
+        v.push(format!("0x{rest:x}"));
+    }
+    v
+}
+
+// accept either a number or comma or space separated names,
+// e.g. "required,guid:60" or "RequiredPartition GUID:60",
+// names may be mixed with hex values, e.g. "required,0x100"
+pub(crate) fn parse_attr(s: &str) -> Result<u64> {
+    if let Ok(v) = parse_u64(s) {
+        return Ok(v);
+    }
+    let mut attr = 0;
+    for x in s.split([',', ' ']).filter(|x| !x.is_empty()) {
+        if x.starts_with("0x") || x.starts_with("0X") {
+            attr |= parse_u64(x)?;
+            continue;
+        }
+        let bit = if let Some(v) = ATTR_NAMES
+            .iter()
+            .find(|a| a.1.eq_ignore_ascii_case(x) || a.2.eq_ignore_ascii_case(x))
+        {
+            v.0
+        } else if let Some(v) = x.strip_prefix("guid:").or_else(|| x.strip_prefix("GUID:")) {
+            match v.parse() {
+                Ok(v @ 48..=63) => v,
+                _ => return Err(format!("invalid GUID specific attribute \"{x}\"").into()),
+            }
+        } else {
+            return Err(format!("unknown attribute \"{x}\"").into());
+        };
+        attr |= 1 << bit;
+    }
+    Ok(attr)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::gpt;
     use crate::uuid;
 
     #[test]
@@ -771,8 +1559,112 @@ mod tests {
                 assert!(!a.name.is_empty());
                 assert!(!b.name.is_empty());
             }
+            assert!(!a.os.is_empty());
+            assert!(!a.desc.is_empty());
         }
         assert_eq!(n, known_uuid.len());
+
+        let x = super::find_known_uuid(
+            &uuid::uuid_from_string("4f68bce3-e8cd-4db1-96e7-fbcaf984b709").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(x.name, "LINUX_ROOT_X86_64");
+        assert_eq!(x.os, "Linux");
+        assert_eq!(x.desc, "Linux root (/) for x86-64");
+        assert!(super::find_known_uuid(&uuid::uuid_random().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_known_index() {
+        for x in super::get_known_uuid() {
+            assert!(std::ptr::eq(super::find_known_uuid(&x.uuid).unwrap(), *x));
+            assert_eq!(super::known_uuid_to_str(&x.uuid), x.name);
+            assert_eq!(super::known_str_to_uuid(x.name), Some(x.uuid));
+            assert_eq!(
+                super::known_str_to_uuid(&x.name.to_ascii_lowercase()),
+                Some(x.uuid)
+            );
+        }
+        assert_eq!(super::known_uuid_to_str(&uuid::uuid_random().unwrap()), "");
+        assert_eq!(super::known_str_to_uuid("NO_SUCH_TYPE"), None);
+    }
+
+    #[test]
+    fn test_search_known_uuid() {
+        assert_eq!(
+            super::search_known_uuid("").len(),
+            super::get_known_uuid().len()
+        );
+        let v = super::search_known_uuid("linux_swap");
+        assert_eq!(v.len(), 1);
+        assert_eq!(
+            super::uuid_to_str(&v[0].uuid),
+            "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f"
+        );
+        let v = super::search_known_uuid("CEPH");
+        assert!(v.len() >= 10 && v.iter().all(|x| x.os == "Ceph"));
+        let v = super::search_known_uuid("ibm");
+        assert!(v.len() >= 2 && v.iter().all(|x| x.os == "IBM"));
+        assert_eq!(super::search_known_uuid("c12a7328")[0].name, "EFI");
+        assert_eq!(
+            super::search_known_uuid("{C12A7328-F81F-11D2-BA4B-00A0C93EC93B}")[0].name,
+            "EFI"
+        );
+        assert_eq!(super::search_known_uuid("x86-64").len(), 4);
+        assert!(super::search_known_uuid("no such type").is_empty());
+    }
+
+    #[test]
+    fn test_parse_types() {
+        let v = super::parse_types(
+            "# comment
+01234567-89ab-cdef-0123-456789abcdef MY_DATA   Private data partition
+
+0fc63daf-8483-4772-8e79-3d69d8477de4 ROOTFS
+",
+            false,
+        )
+        .unwrap();
+        assert_eq!(v.len(), 2);
+        assert_eq!(v[0].name, "MY_DATA");
+        assert_eq!(v[0].desc, "Private data partition");
+        assert_eq!(v[0].os, "User");
+        assert_eq!(v[1].name, "ROOTFS");
+        assert_eq!(v[1].desc, "ROOTFS");
+        assert_eq!(
+            v[1].uuid,
+            uuid::uuid_from_string("0fc63daf-8483-4772-8e79-3d69d8477de4").unwrap()
+        );
+
+        let v = super::parse_types(
+            r#"
+[[types]]
+guid = "01234567-89ab-cdef-0123-456789abcdef"
+name = "MY_DATA"
+description = "Private data partition"
+os = "Acme"
+"#,
+            true,
+        )
+        .unwrap();
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0].os, "Acme");
+        assert_eq!(v[0].desc, "Private data partition");
+
+        for s in [
+            "xxx MY_DATA",
+            "01234567-89ab-cdef-0123-456789abcdef",
+            "01234567-89ab-cdef-0123-456789abcdef 0123456789012345678901234567890123456",
+        ] {
+            assert!(super::parse_types(s, false).is_err(), "{s}");
+        }
+        for s in [
+            "types = 1",
+            "[[types]]\nname = \"X\"",
+            "[[types]]\nguid = \"xxx\"\nname = \"X\"",
+        ] {
+            assert!(super::parse_types(s, true).is_err(), "{s}");
+        }
     }
 
     #[test]
@@ -799,9 +1691,136 @@ mod tests {
     }
 
     #[test]
-    fn test_ds() {
-        assert_eq!(std::mem::size_of::<gpt::GptHdr>(), 92 + 4);
-        assert_eq!(std::mem::size_of::<gpt::GptEnt>(), 128);
-        assert_eq!(std::mem::size_of::<uuid::Uuid>(), 16);
+    fn test_parse_uuid() {
+        let u = super::parse_uuid("FREEBSD").unwrap();
+        assert_eq!(
+            uuid::uuid_to_string(&u),
+            "516e7cb4-6ecf-11d6-8ff8-00022d09712b"
+        );
+        assert_eq!(super::parse_uuid("freebsd").unwrap(), u);
+        assert_eq!(
+            super::parse_uuid("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap(),
+            u
+        );
+        assert!(super::parse_uuid("FREEBSD_XXX").is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(super::parse_size("4096").unwrap(), 4096);
+        assert_eq!(super::parse_size("1K").unwrap(), 1024);
+        assert_eq!(super::parse_size("512M").unwrap(), 512 << 20);
+        assert_eq!(super::parse_size("512MiB").unwrap(), 512 << 20);
+        assert_eq!(super::parse_size("2g").unwrap(), 2 << 30);
+        assert_eq!(super::parse_size("1T").unwrap(), 1 << 40);
+        assert!(super::parse_size("").is_err());
+        assert!(super::parse_size("M").is_err());
+        assert!(super::parse_size("1X").is_err());
+        assert!(super::parse_size("100000000P").is_err());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(super::format_size(0), "0B");
+        assert_eq!(super::format_size(1023), "1023B");
+        assert_eq!(super::format_size(1024), "1.0KiB");
+        assert_eq!(super::format_size(1536 << 20), "1.5GiB");
+        assert_eq!(super::format_size(u64::MAX), "16.0EiB");
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let buf: Vec<u8> = (0x30..0x30 + 20).collect();
+        assert_eq!(
+            super::hexdump(&buf, 0x200),
+            [
+                "00000200  30 31 32 33 34 35 36 37  38 39 3a 3b 3c 3d 3e 3f  |0123456789:;<=>?|",
+                "00000210  40 41 42 43                                       |@ABC|"
+            ]
+        );
+        assert_eq!(
+            super::hexdump(&[0, 0x7f], 0)[0].split('|').nth(1),
+            Some("..")
+        );
+        assert!(super::hexdump(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_parse_index_list() {
+        assert_eq!(super::parse_index_list("3").unwrap(), [3]);
+        assert_eq!(super::parse_index_list("1,3-5").unwrap(), [1, 3, 4, 5]);
+        assert_eq!(super::parse_index_list("2-3, 1,2").unwrap(), [2, 3, 1]);
+        assert!(super::parse_index_list("").is_err());
+        assert!(super::parse_index_list("4-1").is_err());
+        assert!(super::parse_index_list("1,,2").is_err());
+        assert!(super::parse_index_list("1-").is_err());
+    }
+
+    #[test]
+    fn test_parse_attr() {
+        assert_eq!(super::parse_attr("0x4").unwrap(), 4);
+        assert_eq!(super::parse_attr("required").unwrap(), 1);
+        assert_eq!(
+            super::parse_attr("required,legacy-bios-bootable").unwrap(),
+            5
+        );
+        assert_eq!(
+            super::parse_attr("no-block-io,guid:60").unwrap(),
+            2 | 1 << 60
+        );
+        assert_eq!(super::parse_attr("GUID:63").unwrap(), 1 << 63);
+        assert_eq!(
+            super::parse_attr("RequiredPartition LegacyBIOSBootable GUID:48").unwrap(),
+            5 | 1 << 48
+        );
+        assert!(super::parse_attr("guid:47").is_err());
+        assert!(super::parse_attr("bootable").is_err());
+    }
+
+    #[test]
+    fn test_attr_to_names() {
+        assert!(super::attr_to_names(0, false).is_empty());
+        assert_eq!(
+            super::attr_to_names(5 | 1 << 60, false),
+            ["required", "legacy-bios-bootable", "guid:60"]
+        );
+        assert_eq!(
+            super::attr_to_names(2 | 1 << 48 | 1 << 63, true),
+            ["NoBlockIOProtocol", "GUID:48", "GUID:63"]
+        );
+        assert_eq!(
+            super::attr_to_names(1 | 1 << 3 | 1 << 47 | 1 << 48, false),
+            ["required", "guid:48", "0x800000000008"]
+        );
+        assert_eq!(super::attr_to_names(1 << 3, true), [] as [String; 0]);
+        for x in [1, 2, 4, 7, 1 << 48, 3 | 1 << 55 | 1 << 63] {
+            for sfdisk in [false, true] {
+                let s = super::attr_to_names(x, sfdisk).join(",");
+                assert_eq!(super::parse_attr(&s).unwrap(), x);
+            }
+        }
+        for x in [1 << 3, 5 | 1 << 20 | 1 << 60, u64::MAX] {
+            let s = super::attr_to_names(x, false).join(" ");
+            assert_eq!(super::parse_attr(&s).unwrap(), x);
+        }
+        assert!(super::parse_attr("required,0xg").is_err());
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(super::crc32(&[]), 0);
+        assert_eq!(super::crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_parse_u64() {
+        assert_eq!(super::parse_u64("0").unwrap(), 0);
+        assert_eq!(super::parse_u64("2048").unwrap(), 2048);
+        assert_eq!(super::parse_u64("0x800").unwrap(), 2048);
+        assert_eq!(super::parse_u64("0X800").unwrap(), 2048);
+        assert!(super::parse_u64("").is_err());
+        assert!(super::parse_u64("-1").is_err());
+        assert!(super::parse_u64("0x").is_err());
+        assert!(super::parse_u64("1k").is_err());
     }
 }
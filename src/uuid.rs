@@ -1,7 +1,9 @@
+use crate::Result;
+use std::io::Read;
+
 pub(crate) const UUID_NODE_LEN: usize = 6;
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
 pub(crate) struct Uuid {
     pub(crate) time_low: u32,
     pub(crate) time_mid: u16,
@@ -19,6 +21,44 @@ impl Uuid {
     }
 }
 
+const fn hex_at(b: &[u8], start: usize, n: usize) -> u64 {
+    let mut v = 0;
+    let mut i = 0;
+    while i < n {
+        let c = b[start + i];
+        let x = match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            b'A'..=b'F' => c - b'A' + 10,
+            _ => panic!("invalid GUID digit"),
+        };
+        v = v << 4 | x as u64;
+        i += 1;
+    }
+    v
+}
+
+// canonical form only, for GUID constants evaluated at compile time
+pub(crate) const fn uuid_from_str_const(s: &str) -> Uuid {
+    let b = s.as_bytes();
+    assert!(b.len() == 36 && b[8] == b'-' && b[13] == b'-' && b[18] == b'-' && b[23] == b'-');
+    Uuid {
+        time_low: hex_at(b, 0, 8) as u32,
+        time_mid: hex_at(b, 9, 4) as u16,
+        time_hi_and_version: hex_at(b, 14, 4) as u16,
+        clock_seq_hi_and_reserved: hex_at(b, 19, 2) as u8,
+        clock_seq_low: hex_at(b, 21, 2) as u8,
+        node: [
+            hex_at(b, 24, 2) as u8,
+            hex_at(b, 26, 2) as u8,
+            hex_at(b, 28, 2) as u8,
+            hex_at(b, 30, 2) as u8,
+            hex_at(b, 32, 2) as u8,
+            hex_at(b, 34, 2) as u8,
+        ],
+    }
+}
+
 pub(crate) fn uuid_to_string(u: &Uuid) -> String {
     format!(
         "{:08x}-{:04x}-{:04x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
@@ -36,8 +76,237 @@ pub(crate) fn uuid_to_string(u: &Uuid) -> String {
     )
 }
 
+fn uuid_from_be_bytes(v: &[u8; 16]) -> Uuid {
+    Uuid {
+        time_low: u32::from_be_bytes([v[0], v[1], v[2], v[3]]),
+        time_mid: u16::from_be_bytes([v[4], v[5]]),
+        time_hi_and_version: u16::from_be_bytes([v[6], v[7]]),
+        clock_seq_hi_and_reserved: v[8],
+        clock_seq_low: v[9],
+        node: [v[10], v[11], v[12], v[13], v[14], v[15]],
+    }
+}
+
+// accepts 8-4-4-4-12 hex digits in either case, optionally enclosed in
+// braces or prefixed with "urn:uuid:", error positions are 0-based offsets
+// into the original string
+pub(crate) fn uuid_from_string(s: &str) -> Result<Uuid> {
+    let mut b = s.as_bytes();
+    let mut pos = 0;
+    if b.len() >= 9 && b[..9].eq_ignore_ascii_case(b"urn:uuid:") {
+        b = &b[9..];
+        pos = 9;
+    } else if b.first() == Some(&b'{') {
+        if b.last() != Some(&b'}') || b.len() < 2 {
+            return Err(format!("missing closing brace in \"{s}\"").into());
+        }
+        b = &b[1..b.len() - 1];
+        pos = 1;
+    }
+    if b.len() != 36 {
+        return Err(format!("invalid UUID length {} (expected 36) in \"{s}\"", b.len()).into());
+    }
+    let mut v = [0u8; 16];
+    let mut n = 0;
+    for (i, c) in b.iter().enumerate() {
+        if i == 8 || i == 13 || i == 18 || i == 23 {
+            if *c != b'-' {
+                return Err(format!("expected '-' at position {} in \"{s}\"", pos + i).into());
+            }
+            continue;
+        }
+        let Some(x) = char::from(*c).to_digit(16) else {
+            // report the full character rather than a partial UTF-8 byte
+            let c = s[pos + i..].chars().next().unwrap_or('?');
+            return Err(
+                format!("invalid character {c:?} at position {} in \"{s}\"", pos + i).into(),
+            );
+        };
+        v[n / 2] = v[n / 2] << 4 | u8::try_from(x)?;
+        n += 1;
+    }
+    Ok(uuid_from_be_bytes(&v))
+}
+
+// GPT on-disk form, the first three fields are little-endian
+pub(crate) fn uuid_from_le_bytes(v: &[u8; 16]) -> Uuid {
+    Uuid {
+        time_low: u32::from_le_bytes([v[0], v[1], v[2], v[3]]),
+        time_mid: u16::from_le_bytes([v[4], v[5]]),
+        time_hi_and_version: u16::from_le_bytes([v[6], v[7]]),
+        clock_seq_hi_and_reserved: v[8],
+        clock_seq_low: v[9],
+        node: [v[10], v[11], v[12], v[13], v[14], v[15]],
+    }
+}
+
+pub(crate) fn uuid_to_le_bytes(u: &Uuid) -> [u8; 16] {
+    let mut v = [0u8; 16];
+    v[..4].copy_from_slice(&u.time_low.to_le_bytes());
+    v[4..6].copy_from_slice(&u.time_mid.to_le_bytes());
+    v[6..8].copy_from_slice(&u.time_hi_and_version.to_le_bytes());
+    v[8] = u.clock_seq_hi_and_reserved;
+    v[9] = u.clock_seq_low;
+    v[10..].copy_from_slice(&u.node);
+    v
+}
+
+fn uuid_to_be_bytes(u: &Uuid) -> [u8; 16] {
+    let mut v = [0u8; 16];
+    v[..4].copy_from_slice(&u.time_low.to_be_bytes());
+    v[4..6].copy_from_slice(&u.time_mid.to_be_bytes());
+    v[6..8].copy_from_slice(&u.time_hi_and_version.to_be_bytes());
+    v[8] = u.clock_seq_hi_and_reserved;
+    v[9] = u.clock_seq_low;
+    v[10..].copy_from_slice(&u.node);
+    v
+}
+
+// RFC 4122 appendix C
+pub(crate) const NAMESPACE_DNS: Uuid = uuid_from_str_const("6ba7b810-9dad-11d1-80b4-00c04fd430c8");
+pub(crate) const NAMESPACE_URL: Uuid = uuid_from_str_const("6ba7b811-9dad-11d1-80b4-00c04fd430c8");
+pub(crate) const NAMESPACE_OID: Uuid = uuid_from_str_const("6ba7b812-9dad-11d1-80b4-00c04fd430c8");
+pub(crate) const NAMESPACE_X500: Uuid = uuid_from_str_const("6ba7b814-9dad-11d1-80b4-00c04fd430c8");
+
+// namespace of GUIDs derived from --seed
+pub(crate) const NAMESPACE_SEED: Uuid = uuid_from_str_const("8d1f7c2e-3b0a-4e55-9c61-2f4a7e0d9b13");
+
+pub(crate) fn uuid_v5(namespace: &Uuid, name: &[u8]) -> Uuid {
+    let mut h = sha1_smol::Sha1::new();
+    h.update(&uuid_to_be_bytes(namespace));
+    h.update(name);
+    let mut v = [0u8; 16];
+    v.copy_from_slice(&h.digest().bytes()[..16]);
+    v[6] = (v[6] & 0x0f) | 0x50; // version 5
+    v[8] = (v[8] & 0x3f) | 0x80; // RFC 4122 variant
+    uuid_from_be_bytes(&v)
+}
+
+pub(crate) fn uuid_random() -> Result<Uuid> {
+    let mut v = [0u8; 16];
+    std::fs::File::open("/dev/urandom")?.read_exact(&mut v)?;
+    v[6] = (v[6] & 0x0f) | 0x40; // version 4
+    v[8] = (v[8] & 0x3f) | 0x80; // RFC 4122 variant
+    Ok(uuid_from_be_bytes(&v))
+}
+
+pub(crate) fn uuid_version(u: &Uuid) -> u8 {
+    (u.time_hi_and_version >> 12) as u8
+}
+
+pub(crate) fn uuid_variant(u: &Uuid) -> &'static str {
+    match u.clock_seq_hi_and_reserved >> 5 {
+        0..=3 => "NCS",
+        4 | 5 => "RFC 4122",
+        6 => "Microsoft",
+        _ => "future",
+    }
+}
+
+fn version_to_str(version: u8) -> &'static str {
+    match version {
+        1 => "time-based",
+        2 => "DCE security",
+        3 => "name-based MD5",
+        4 => "random",
+        5 => "name-based SHA-1",
+        6 => "reordered time-based",
+        7 => "Unix time-based",
+        8 => "custom",
+        _ => "unknown",
+    }
+}
+
+// 100ns intervals between 1582-10-15 and 1970-01-01
+const GREGORIAN_OFFSET: i64 = 0x01b2_1dd2_1381_4000;
+
+// timestamp of a v1, v6 or v7 UUID in 100ns intervals since the Unix epoch
+pub(crate) fn uuid_time(u: &Uuid) -> Option<i64> {
+    if uuid_variant(u) != "RFC 4122" {
+        return None;
+    }
+    let hi = u64::from(u.time_hi_and_version & 0x0fff);
+    let mid = u64::from(u.time_mid);
+    let low = u64::from(u.time_low);
+    let t = match uuid_version(u) {
+        1 => hi << 48 | mid << 32 | low,
+        6 => low << 28 | mid << 12 | hi,
+        7 => return i64::try_from((low << 16 | mid) * 10_000).ok(),
+        _ => return None,
+    };
+    Some(i64::try_from(t).ok()? - GREGORIAN_OFFSET)
+}
+
+// days since 1970-01-01 to (year, month, day) in the proleptic Gregorian calendar
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+pub(crate) fn time_to_string(t: i64) -> String {
+    let secs = t.div_euclid(10_000_000);
+    let frac = t.rem_euclid(10_000_000);
+    let (y, m, d) = civil_from_days(secs.div_euclid(86400));
+    let x = secs.rem_euclid(86400);
+    format!(
+        "{y:04}-{m:02}-{d:02} {:02}:{:02}:{:02}.{frac:07} UTC",
+        x / 3600,
+        x / 60 % 60,
+        x % 60
+    )
+}
+
+// human readable decoding of variant, version and embedded v1 fields
+pub(crate) fn uuid_describe(u: &Uuid) -> Vec<String> {
+    if *u == Uuid::new() {
+        return vec!["nil UUID".to_string()];
+    }
+    let variant = uuid_variant(u);
+    if variant != "RFC 4122" {
+        return vec![format!("variant {variant}")];
+    }
+    let version = uuid_version(u);
+    let mut v = vec![format!(
+        "variant {variant}, version {version} ({})",
+        version_to_str(version)
+    )];
+    if let Some(t) = uuid_time(u) {
+        v.push(format!("time {}", time_to_string(t)));
+    }
+    if version == 1 || version == 6 {
+        let clock_seq =
+            u16::from(u.clock_seq_hi_and_reserved & 0x3f) << 8 | u16::from(u.clock_seq_low);
+        let mac: Vec<_> = u.node.iter().map(|b| format!("{b:02x}")).collect();
+        // multicast bit set means the node is random rather than a MAC address
+        v.push(format!(
+            "clock_seq {clock_seq}, node {}{}",
+            mac.join(":"),
+            if u.node[0] & 1 != 0 { " (random)" } else { "" }
+        ));
+    }
+    v
+}
+
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn test_uuid_from_str_const() {
+        const U: super::Uuid = super::uuid_from_str_const("C12A7328-F81F-11D2-BA4B-00A0C93EC93B");
+        assert_eq!(
+            U,
+            super::uuid_from_string("c12a7328-f81f-11d2-ba4b-00a0c93ec93b").unwrap()
+        );
+        assert_eq!(U.time_low, 0xc12a_7328);
+        assert_eq!(U.node, [0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b]);
+    }
+
     #[test]
     fn test_uuid_to_string() {
         let u = super::Uuid::new();
@@ -59,4 +328,189 @@ mod tests {
             "516e7cb4-6ecf-11d6-8ff8-00022d09712b"
         );
     }
+
+    #[test]
+    fn test_uuid_from_string() {
+        let u = super::uuid_from_string("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap();
+        assert_eq!(
+            u,
+            super::Uuid {
+                time_low: 0x516e_7cb4,
+                time_mid: 0x6ecf,
+                time_hi_and_version: 0x11d6,
+                clock_seq_hi_and_reserved: 0x8f,
+                clock_seq_low: 0xf8,
+                node: [0x00, 0x02, 0x2d, 0x09, 0x71, 0x2b],
+            }
+        );
+        assert_eq!(
+            super::uuid_to_string(&u),
+            "516e7cb4-6ecf-11d6-8ff8-00022d09712b"
+        );
+
+        for s in [
+            "516E7CB4-6ECF-11D6-8FF8-00022D09712B",
+            "{516e7cb4-6ecf-11d6-8ff8-00022d09712b}",
+            "urn:uuid:516e7cb4-6ecf-11d6-8ff8-00022d09712b",
+            "URN:UUID:516e7cb4-6EcF-11d6-8ff8-00022d09712b",
+        ] {
+            assert_eq!(super::uuid_from_string(s).unwrap(), u, "{s}");
+        }
+
+        for (s, e) in [
+            ("", "invalid UUID length 0 (expected 36) in \"\""),
+            (
+                "516e7cb4-6ecf-11d6-8ff8-00022d09712",
+                "invalid UUID length 35 (expected 36) in \"516e7cb4-6ecf-11d6-8ff8-00022d09712\"",
+            ),
+            (
+                "516e7cb4-6ecf-11d6-8ff8+00022d09712b",
+                "expected '-' at position 23 in \"516e7cb4-6ecf-11d6-8ff8+00022d09712b\"",
+            ),
+            (
+                "516e7cb4-6ecf-11d6-8ff8-00022d09712x",
+                "invalid character 'x' at position 35 in \"516e7cb4-6ecf-11d6-8ff8-00022d09712x\"",
+            ),
+            (
+                "{516e7cb4-6ecf-11d6-8ff8-00022d09712b",
+                "missing closing brace in \"{516e7cb4-6ecf-11d6-8ff8-00022d09712b\"",
+            ),
+            (
+                "{516e7cb4-6ecf-11d6-8ff8-00022d0971g2}",
+                "invalid character 'g' at position 35 in \"{516e7cb4-6ecf-11d6-8ff8-00022d0971g2}\"",
+            ),
+            (
+                "urn:uuid:516e7cb4-6ecf-11d6-8ff8-00022d09712\u{e9}",
+                "invalid UUID length 37 (expected 36) in \"urn:uuid:516e7cb4-6ecf-11d6-8ff8-00022d09712\u{e9}\"",
+            ),
+            (
+                "urn:uuid:516e7cb4-6ecf-11d6-8ff8-00022d0971\u{e9}",
+                "invalid character '\u{e9}' at position 43 in \"urn:uuid:516e7cb4-6ecf-11d6-8ff8-00022d0971\u{e9}\"",
+            ),
+        ] {
+            assert_eq!(super::uuid_from_string(s).unwrap_err().to_string(), e);
+        }
+
+        // round trip
+        for _ in 0..16 {
+            let u = super::uuid_random().unwrap();
+            let s = super::uuid_to_string(&u);
+            assert_eq!(super::uuid_from_string(&s).unwrap(), u);
+            let s = format!("{{{}}}", s.to_uppercase());
+            assert_eq!(super::uuid_from_string(&s).unwrap(), u);
+        }
+    }
+
+    #[test]
+    fn test_uuid_le_bytes() {
+        let u = super::uuid_from_string("c12a7328-f81f-11d2-ba4b-00a0c93ec93b").unwrap();
+        let b = [
+            0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e,
+            0xc9, 0x3b,
+        ];
+        assert_eq!(super::uuid_to_le_bytes(&u), b);
+        assert_eq!(super::uuid_from_le_bytes(&b), u);
+        assert_eq!(super::uuid_to_be_bytes(&u)[..4], [0xc1, 0x2a, 0x73, 0x28]);
+    }
+
+    #[test]
+    fn test_uuid_v5() {
+        // values from Python's uuid.uuid5()
+        let u = super::uuid_v5(&super::NAMESPACE_DNS, b"python.org");
+        assert_eq!(
+            super::uuid_to_string(&u),
+            "886313e1-3b8a-5372-9b90-0c9aee199e5d"
+        );
+        let u = super::uuid_v5(&super::NAMESPACE_URL, b"http://example.com/");
+        assert_eq!(
+            super::uuid_to_string(&u),
+            "0a300ee9-f9e4-5697-a51a-efc7fafaba67"
+        );
+        assert_eq!(super::uuid_version(&u), 5);
+        assert_eq!(super::uuid_variant(&u), "RFC 4122");
+        assert_eq!(
+            super::uuid_v5(&super::NAMESPACE_SEED, b"a"),
+            super::uuid_v5(&super::NAMESPACE_SEED, b"a")
+        );
+        assert_ne!(
+            super::uuid_v5(&super::NAMESPACE_SEED, b"a"),
+            super::uuid_v5(&super::NAMESPACE_OID, b"a")
+        );
+        assert_ne!(
+            super::uuid_v5(&super::NAMESPACE_SEED, b"a"),
+            super::uuid_v5(&super::NAMESPACE_X500, b"a")
+        );
+    }
+
+    #[test]
+    fn test_uuid_random() {
+        let a = super::uuid_random().unwrap();
+        let b = super::uuid_random().unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a.time_hi_and_version >> 12, 4);
+        assert_eq!(a.clock_seq_hi_and_reserved >> 6, 2);
+    }
+
+    #[test]
+    fn test_uuid_describe() {
+        let u = super::uuid_from_string("516e7cb4-6ecf-11d6-8ff8-00022d09712b").unwrap();
+        assert_eq!(super::uuid_version(&u), 1);
+        assert_eq!(super::uuid_variant(&u), "RFC 4122");
+        assert_eq!(
+            super::uuid_describe(&u),
+            [
+                "variant RFC 4122, version 1 (time-based)",
+                "time 2002-05-24 04:32:55.7489332 UTC",
+                "clock_seq 4088, node 00:02:2d:09:71:2b",
+            ]
+        );
+
+        let u = super::uuid_from_string("c12a7328-f81f-11d2-ba4b-00a0c93ec93b").unwrap();
+        assert_eq!(
+            super::uuid_describe(&u)[1],
+            "time 1999-04-21 19:24:01.5625000 UTC"
+        );
+
+        let u = super::uuid_from_string("1ec9414c-232a-6b00-b3c8-9f6bdeced846").unwrap();
+        assert_eq!(
+            super::uuid_describe(&u),
+            [
+                "variant RFC 4122, version 6 (reordered time-based)",
+                "time 2022-02-22 19:22:22.0000000 UTC",
+                "clock_seq 13256, node 9f:6b:de:ce:d8:46 (random)",
+            ]
+        );
+
+        let u = super::uuid_from_string("017f22e2-79b0-7cc3-98c4-dc0c0c07398f").unwrap();
+        assert_eq!(
+            super::uuid_describe(&u),
+            [
+                "variant RFC 4122, version 7 (Unix time-based)",
+                "time 2022-02-22 19:22:22.0000000 UTC",
+            ]
+        );
+
+        let u = super::uuid_from_string("ebd0a0a2-b9e5-4433-87c0-68b6b72699c7").unwrap();
+        assert_eq!(
+            super::uuid_describe(&u),
+            ["variant RFC 4122, version 4 (random)"]
+        );
+        assert_eq!(super::uuid_time(&u), None);
+        let u = super::uuid_from_string("00000000-0000-0000-c000-000000000046").unwrap();
+        assert_eq!(super::uuid_describe(&u), ["variant Microsoft"]);
+        assert_eq!(super::uuid_describe(&super::Uuid::new()), ["nil UUID"]);
+    }
+
+    #[test]
+    fn test_time_to_string() {
+        assert_eq!(super::time_to_string(0), "1970-01-01 00:00:00.0000000 UTC");
+        assert_eq!(
+            super::time_to_string(-super::GREGORIAN_OFFSET),
+            "1582-10-15 00:00:00.0000000 UTC"
+        );
+        assert_eq!(
+            super::time_to_string(951_782_400 * 10_000_000 + 1),
+            "2000-02-29 00:00:00.0000001 UTC"
+        );
+    }
 }